@@ -16,11 +16,12 @@ use glutin::{
     event::WindowEvent,
     event_loop::ControlFlow
 };
-use epi::backend::RepaintSignal;
+use epi::{backend::RepaintSignal, file_storage::FileStorage};
 use egui_winit::winit;
 
 use crate::components::App as AppComponent;
-use crate::data::Tick;
+use crate::data::{Tick, Keymap, MatrixPresets, USER_MATRIX_PRESETS};
+use crate::constants::{KEYMAP_FILE_NAME, MATRIX_PRESETS_FILE_NAME};
 
 /// Creates an opengl window and gl context
 fn create_gl_display(
@@ -61,16 +62,34 @@ pub fn run(name : &str, native_options: &epi::NativeOptions) -> ! {
     let event_loop = winit::event_loop::EventLoop::with_user_event();
     let (gl_window, gl) = create_gl_display(window_builder, &event_loop);
     let repaint_signal = Arc::new(GlowRepaintSignal(Mutex::new(event_loop.create_proxy())));
-    let mut app = App::new(gl_window.window(), repaint_signal);
     let mut egui_glow = egui_glow::EguiGlow::new(gl_window.window(), &gl);
     
+    // egui memory holds sizes of panels and other ui state
+    if let Some(memory) = persistence.load_memory() {
+        *egui_glow.egui_ctx.memory() = memory;
+    }
+    let config_dir = directories_next::ProjectDirs::from("", "", name).map(|dirs| dirs.config_dir().to_path_buf());
+    let (keymap, mut config_problems) = config_dir
+        .as_ref()
+        .map(|dir| Keymap::load(&dir.join(KEYMAP_FILE_NAME)))
+        .unwrap_or_default();
+    if let Some(dir) = &config_dir {
+        let (presets, problems) = MatrixPresets::load(&dir.join(MATRIX_PRESETS_FILE_NAME));
+        *USER_MATRIX_PRESETS.lock().unwrap() = presets;
+        config_problems.extend(problems);
+    }
+    let mut app = App::new(gl_window.window(), repaint_signal, FileStorage::from_app_name(name), keymap, config_problems);
+    
     let mut scheduled_tick = Tick::now();
     event_loop.run(move |event, _, control_flow| {
         match event {
             glutin::event::Event::RedrawEventsCleared if cfg!(windows) => app.draw(&gl_window, &gl, &mut egui_glow, control_flow),
             glutin::event::Event::RedrawRequested(_) if !cfg!(windows) => app.draw(&gl_window, &gl, &mut egui_glow, control_flow),
             glutin::event::Event::WindowEvent { event, .. } => app.update(event, &gl_window, &gl, &mut egui_glow, control_flow),
-            glutin::event::Event::LoopDestroyed => egui_glow.destroy(&gl),
+            glutin::event::Event::LoopDestroyed => {
+                app.save(gl_window.window(), &egui_glow.egui_ctx);
+                egui_glow.destroy(&gl);
+            },
             glutin::event::Event::UserEvent(RequestRepaintEvent) => gl_window.window().request_redraw(),
             _ => {
                 // when nothings is happening then slow down refreshing to once in every 100 ms
@@ -88,6 +107,9 @@ pub fn run(name : &str, native_options: &epi::NativeOptions) -> ! {
 pub struct App {
     frame: epi::Frame,
     app: AppComponent,
+    
+    /// storage of window settings, egui memory and session of the app between runs
+    storage: Option<FileStorage>,
 }
 
 impl App {
@@ -97,7 +119,15 @@ impl App {
     pub fn new(
         window: &winit::window::Window,
         repaint_signal: Arc<dyn RepaintSignal>,
+        storage: Option<FileStorage>,
+        keymap: Keymap,
+        keymap_problems: Vec<String>,
     ) -> Self {
+        let session = storage
+            .as_ref()
+            .and_then(|s| epi::get_value(s, epi::APP_KEY))
+            .unwrap_or_default();
+        
         Self {
             frame: epi::Frame::new(epi::backend::FrameData {
                 info: epi::IntegrationInfo {
@@ -110,7 +140,19 @@ impl App {
                 output: Default::default(),
                 repaint_signal: repaint_signal.clone(),
             }),
-            app: AppComponent::new(repaint_signal),
+            app: AppComponent::new(repaint_signal, session).with_keymap(keymap, keymap_problems),
+            storage,
+        }
+    }
+    
+    /// Stores state of the window, ui and app to be restored in next run
+    pub fn save(&mut self, window: &winit::window::Window, egui_ctx: &egui::Context) {
+        if let Some(storage) = &mut self.storage {
+            // keys are the same as used by `egui_winit::epi::Persistence` for loading
+            epi::set_value(storage, "window", &egui_winit::WindowSettings::from_display(window));
+            epi::set_value(storage, "egui", &*egui_ctx.memory());
+            epi::set_value(storage, epi::APP_KEY, &self.app.session());
+            epi::Storage::flush(storage);
         }
     }
     
@@ -163,7 +205,13 @@ impl App {
         control_flow: &mut ControlFlow,
     ) {
         
-        if matches!(event, WindowEvent::CloseRequested | WindowEvent::Destroyed) {
+        // closing is confirmed by the app when there are unsaved changes
+        match event {
+            WindowEvent::CloseRequested => self.app.request_quit(),
+            WindowEvent::Destroyed => *control_flow = glutin::event_loop::ControlFlow::Exit,
+            _ => {},
+        }
+        if self.app.should_quit() {
             *control_flow = glutin::event_loop::ControlFlow::Exit;
         }
         
@@ -1,26 +1,73 @@
 
-use std::sync::Arc;
+use std::{sync::Arc, collections::VecDeque};
 
 use epi::backend::RepaintSignal;
 
 use crate::utils::load_input_file;
-use crate::widgets::{CenteredWindow, FileDropper, texts, BigButton};
-use crate::data::{MultimediaFile, Tick};
+use crate::widgets::{CenteredWindow, FileDropper, texts, BigButton, Toasts};
+use crate::data::{Tick, Keymap, Action};
 
-use super::ImageEditor;
+use super::{ImageEditor, ImageLoader, Recipe, Filmstrip, FilmstripAction, Session, DocumentSession, MAX_RECENT_FILES};
 
 #[cfg(debug_assertions)]
 use super::debug;
 
+/// Action which discards edits of some tabs, it is performed after the user decides what to do with unsaved changes
+enum GuardedAction {
+    Quit,
+    /// closes tab with editor of given id
+    CloseTab(u64),
+    /// loaded image will replace editor of the tab given by the loader
+    Replace(ImageLoader),
+    /// editor loaded in background replaces editor with given id which was edited during loading
+    ReplaceLoaded(u64, Box<ImageEditor>),
+}
+
+struct UnsavedChangesGuard {
+    action: GuardedAction,
+    
+    /// tabs whose changes the user decided to throw away
+    discarded: Vec<usize>,
+}
+
 pub struct App {
     
     // global apps settings
     should_quit: bool,
-    error_message: Option<String>,
+    toasts: Toasts,
     repaint_signal: Arc<dyn RepaintSignal>,
     
     // ui components
-    editor: Option<ImageEditor>,
+    /// open documents, each tab has its own editor
+    editors: Vec<ImageEditor>,
+    active_editor: usize,
+    
+    /// recipe copied from one of the tabs to be pasted into another
+    copied_recipe: Option<Recipe>,
+    
+    /// image being loaded in background, current editor stays usable until it is finished
+    loader: Option<ImageLoader>,
+    
+    /// images in directory of active editor
+    filmstrip: Option<Filmstrip>,
+    /// when set then modifier settings are carried over when moving to another image of filmstrip
+    carry_recipe: bool,
+    
+    /// action waiting for confirmation of unsaved changes
+    guard: Option<UnsavedChangesGuard>,
+    /// actions requested while another action waits for confirmation
+    guard_queue: VecDeque<GuardedAction>,
+    
+    /// keyboard shortcuts of actions
+    keymap: Keymap,
+    show_cheat_sheet: bool,
+    
+    /// recently opened files, most recent first
+    recent_files: Vec<String>,
+    /// documents of previous session waiting to be loaded one by one
+    restore_queue: VecDeque<DocumentSession>,
+    /// tab to be activated when all documents are restored
+    restore_active: Option<usize>,
     
     #[cfg(debug_assertions)]
     debug_bottom_panel: debug::BottomPanel,
@@ -28,12 +75,25 @@ pub struct App {
 
 // constructors
 impl App {
-    pub fn new(repaint_signal: Arc<dyn RepaintSignal>) -> Self {
+    /// Creates the app and starts restoring documents of previous session
+    pub fn new(repaint_signal: Arc<dyn RepaintSignal>, session: Session) -> Self {
         Self {
+            keymap: Default::default(),
+            show_cheat_sheet: Default::default(),
+            recent_files: session.recent_files,
+            restore_active: Some(session.active_document),
+            restore_queue: session.documents.into(),
             repaint_signal,
             should_quit: Default::default(),
-            error_message: Default::default(),
-            editor: Default::default(),
+            toasts: Default::default(),
+            editors: Default::default(),
+            active_editor: Default::default(),
+            copied_recipe: Default::default(),
+            loader: Default::default(),
+            filmstrip: Default::default(),
+            carry_recipe: Default::default(),
+            guard: Default::default(),
+            guard_queue: Default::default(),
             
             #[cfg(debug_assertions)]
             debug_bottom_panel: Default::default(),
@@ -44,6 +104,28 @@ impl App {
 // Data manipulation
 impl App {
     
+    /// uses keymap loaded from config file and reports problems found in the file
+    pub fn with_keymap(mut self, keymap: Keymap, problems: Vec<String>) -> Self {
+        self.keymap = keymap;
+        problems.into_iter().for_each(|p| self.toasts.warning(p));
+        self
+    }
+    
+    /// performs action triggered by keyboard shortcut
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Open => if let Some(file_name) = load_input_file() {
+                self.load_image_from_file_name(file_name.as_str());
+            },
+            Action::NextImage => self.open_neighbor(1),
+            Action::PreviousImage => self.open_neighbor(-1),
+            Action::CheatSheet => self.show_cheat_sheet = !self.show_cheat_sheet,
+            _ => if let Some(editor) = self.editors.get_mut(self.active_editor) {
+                editor.perform(action, &mut self.toasts);
+            },
+        }
+    }
+    
     /// Get the app's should quit.
     pub fn should_quit(&self) -> bool {
         self.should_quit
@@ -54,14 +136,179 @@ impl App {
         self.should_quit = true;
     }
     
-    /// tries to loaf image from file name
+    /// state of the app to be restored in next run
+    pub fn session(&self) -> Session {
+        Session {
+            recent_files: self.recent_files.clone(),
+            documents: self.editors.iter().map(|e| e.session()).collect(),
+            active_document: self.active_editor,
+        }
+    }
+    
+    /// quits after the user confirms what to do with unsaved changes
+    pub fn request_quit(&mut self) {
+        self.guard_action(GuardedAction::Quit);
+    }
+    
+    /// postpones the action until all affected tabs are saved or discarded,
+    ///   action requested while another one waits for confirmation is queued after it
+    fn guard_action(&mut self, action: GuardedAction) {
+        self.guard_queue.push_back(action);
+        self.resolve_guard();
+    }
+    
+    /// returns index of first tab affected by guarded action which still has unsaved changes,
+    ///   when there is no such tab the action is performed and the next queued action is resolved
+    fn resolve_guard(&mut self) -> Option<usize> {
+        if self.guard.is_none() {
+            let action = self.guard_queue.pop_front()?;
+            self.guard = Some(UnsavedChangesGuard { action, discarded: vec![] });
+        }
+        
+        let guard = self.guard.as_ref()?;
+        let affected: Vec<usize> = match &guard.action {
+            GuardedAction::Quit => (0..self.editors.len()).collect(),
+            GuardedAction::CloseTab(id) => self.editor_index(*id).into_iter().collect(),
+            GuardedAction::Replace(loader) => loader.replaced_editor().and_then(|id| self.editor_index(id)).into_iter().collect(),
+            GuardedAction::ReplaceLoaded(id, _) => self.editor_index(*id).into_iter().collect(),
+        };
+        
+        let unsaved = affected
+            .into_iter()
+            .find(|i| !guard.discarded.contains(i) && self.editors.get(*i).is_some_and(|e| e.is_dirty()));
+        if unsaved.is_some() {
+            return unsaved;
+        }
+        
+        match self.guard.take().map(|g| g.action) {
+            Some(GuardedAction::Quit) => self.quit(),
+            Some(GuardedAction::CloseTab(id)) => if let Some(tab) = self.editor_index(id) {
+                self.close_editor(tab);
+            },
+            Some(GuardedAction::Replace(mut loader)) => {
+                if let Some(editor) = loader.replaced_editor().and_then(|id| self.editor_index(id)) {
+                    loader.set_replaced_recipe(self.editors[editor].recipe());
+                }
+                self.set_loader(loader);
+            },
+            Some(GuardedAction::ReplaceLoaded(id, editor)) => self.install_editor(*editor, Some(id)),
+            None => {},
+        }
+        self.resolve_guard()
+    }
+    
+    /// index of tab with editor of given id
+    fn editor_index(&self, id: u64) -> Option<usize> {
+        self.editors.iter().position(|e| e.id() == id)
+    }
+    
+    /// shows editor in place of editor with given id or in a new tab when there is no such editor
+    fn install_editor(&mut self, editor: ImageEditor, replaced: Option<u64>) {
+        match replaced.and_then(|id| self.editor_index(id)) {
+            Some(tab) => {
+                self.editors[tab] = editor;
+                self.active_editor = tab;
+            },
+            None => {
+                self.editors.push(editor);
+                self.active_editor = self.editors.len() - 1;
+            },
+        }
+    }
+    
+    /// starts loading of image from file name in background, replaces image being currently loaded
     pub fn load_image_from_file_name(&mut self, s : &str) {
-        (self.editor, self.error_message) = match MultimediaFile::from_file(s) {
-            Ok(image_file) => match ImageEditor::from_file(image_file, self.repaint_signal.clone()) {
-                Ok(state) => (Some(state), None),
-                Err(message) => ( None, Some(message)),
+        self.set_loader(ImageLoader::start(s, self.repaint_signal.clone()));
+    }
+    
+    /// replaces current loader, interrupted document of previous session is queued to be restored later
+    fn set_loader(&mut self, loader: ImageLoader) {
+        if let Some(session) = self.loader.replace(loader).and_then(|l| l.session().cloned()) {
+            self.restore_queue.push_front(session);
+        }
+    }
+    
+    /// loads another image into active tab, optionally with settings of the current one
+    pub fn open_in_active_tab(&mut self, file_name: &str) {
+        let recipe = match self.carry_recipe {
+            true => self.editors.get(self.active_editor).map(|e| e.recipe()),
+            false => None,
+        };
+        let id = match self.editors.get(self.active_editor) {
+            Some(editor) => editor.id(),
+            None => return self.load_image_from_file_name(file_name),
+        };
+        let loader = ImageLoader::start(file_name, self.repaint_signal.clone()).replacing(id, recipe);
+        self.guard_action(GuardedAction::Replace(loader));
+    }
+    
+    /// stops loading of image, the thread finishes its current stage and result is thrown away
+    pub fn cancel_loading(&mut self) {
+        self.loader = None;
+    }
+    
+    /// loads documents of previous session one after another when no other image is being loaded
+    fn restore_next_document(&mut self) {
+        if self.loader.is_some() {
+            return;
+        }
+        match self.restore_queue.pop_front() {
+            Some(document) => self.loader = Some(ImageLoader::restoring(document, self.repaint_signal.clone())),
+            None => if let Some(active) = self.restore_active.take() {
+                self.active_editor = active.min(self.editors.len().saturating_sub(1));
+            },
+        }
+    }
+    
+    /// moves file to the top of recent files
+    fn add_recent_file(&mut self, path: String) {
+        self.recent_files.retain(|f| *f != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+    
+    /// opens loaded image in its tab when loading is finished
+    fn poll_loader(&mut self) {
+        self.restore_next_document();
+        
+        let result = match self.loader.as_mut().and_then(|l| l.poll()) {
+            Some(result) => result,
+            None => return,
+        };
+        let loader = self.loader.take();
+        
+        match result {
+            Ok(mut editor) => {
+                if let Some(recipe) = loader.as_ref().and_then(|l| l.recipe()) {
+                    editor.apply_recipe(recipe);
+                }
+                if let Some(session) = loader.as_ref().and_then(|l| l.session()) {
+                    editor.restore(session);
+                }
+                self.add_recent_file(editor.path().display().to_string());
+                let replaced = loader.as_ref().and_then(|l| l.replaced_editor());
+                
+                // the replaced editor stayed usable while loading, changes made meanwhile need confirmation again
+                let edited = replaced
+                    .and_then(|id| self.editor_index(id))
+                    .map(|tab| &self.editors[tab])
+                    .is_some_and(|e| e.is_dirty() && Some(&e.recipe()) != loader.as_ref().and_then(|l| l.replaced_recipe()));
+                match (replaced, edited) {
+                    (Some(id), true) => self.guard_action(GuardedAction::ReplaceLoaded(id, Box::new(editor))),
+                    _ => self.install_editor(editor, replaced),
+                }
             },
-            Err(error) => (None, Some(format!("{}", error))),
+            Err(message) => self.toasts.error(message),
+        }
+    }
+    
+    /// closes tab on given index and activates its neighbor
+    pub fn close_editor(&mut self, index: usize) {
+        if index < self.editors.len() {
+            self.editors.remove(index);
+            if self.active_editor > index || self.active_editor >= self.editors.len() {
+                self.active_editor = self.active_editor.saturating_sub(1);
+            }
         }
     }
     
@@ -87,21 +334,26 @@ impl App {
             self.debug_bottom_panel.ui(ctx, frame); // separate method to be able to insert it deeper into ui hierarchy
         }
         
-        if self.error_message.is_some() {
-            
-            // TODO: render error message as overlay
-            
-            // Render error of invalid file
-            CenteredWindow::show(ctx, |ui| {
-                // let msg = message.clone();
-                ui.label(texts::big("Selected file is invalid"));
-                ui.label(format!("{}", self.error_message.as_ref().unwrap()));
-                self.open_file_dialog(ui);
-            });
+        self.poll_loader();
+        
+        for action in self.keymap.triggered(ctx) {
+            self.perform(action);
+        }
+        let hold_original = self.keymap.is_down(ctx, Action::ShowOriginal);
+        if let Some(editor) = self.editors.get_mut(self.active_editor) {
+            editor.set_hold_original(hold_original);
+        }
+        
+        if !self.editors.is_empty() {
+            self.tabs_ui(ctx);
+        }
+        
+        // closing a tab may have removed the last editor
+        if !self.editors.is_empty() {
             
-        } else if let Some(editor) = &mut self.editor {
+            self.filmstrip_ui(ctx);
             
-            match editor.ui(ctx, frame) {
+            match self.editors[self.active_editor].ui(ctx, frame, &mut self.toasts) {
                 super::EditorResult::Nothing => {},
                 super::EditorResult::LoadNewImage => {
                     if let Some(file_name) = load_input_file() {
@@ -117,7 +369,226 @@ impl App {
                 ui.label(texts::big("Select image to edit"));
                 ui.add_space(10.0);
                 self.open_file_dialog(ui);
+                
+                if !self.recent_files.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(texts::sized("Recent files:", 17.0));
+                    if let Some(file_name) = self.recent_files_ui(ui) {
+                        self.load_image_from_file_name(&file_name);
+                    }
+                }
+            });
+        }
+        
+        self.loading_ui(ctx);
+        self.unsaved_changes_ui(ctx);
+        self.cheat_sheet_ui(ctx);
+        self.toasts.ui(ctx);
+    }
+    
+    /// asks the user whether to save changes of tab affected by guarded action
+    fn unsaved_changes_ui(&mut self, ctx: &egui::Context) {
+        let tab = match self.resolve_guard() {
+            Some(tab) => tab,
+            None => return,
+        };
+        
+        let mut save = false;
+        let mut discard = false;
+        let mut cancel = false;
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("Changes of \"{}\" were not saved.", self.editors[tab].title()));
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    save = ui.button("💾  Save").clicked();
+                    discard = ui.button("Discard").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if save {
+            // when saving fails or the user cancels the file dialog the tab stays unsaved and the question is asked again
+            self.editors[tab].save_and_notify(&mut self.toasts, false);
+        } else if discard {
+            if let Some(guard) = &mut self.guard {
+                guard.discarded.push(tab);
+            }
+        } else if cancel {
+            self.guard = None;
+        }
+    }
+    
+    /// bar with tab of every opened image and recipe copying
+    fn tabs_ui(&mut self, ctx: &egui::Context) {
+        let mut close = None;
+        let mut open_recent = None;
+        
+        egui::TopBottomPanel::top("tabs_bar")
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.menu_button("🕘 Recent", |ui| {
+                        if let Some(file_name) = self.recent_files_ui(ui) {
+                            open_recent = Some(file_name);
+                            ui.close_menu();
+                        }
+                    });
+                    
+                    for (i, editor) in self.editors.iter().enumerate() {
+                        ui.group(|ui| {
+                            // unsaved changes are marked with a dot like in common editors
+                            let title = match editor.is_dirty() {
+                                true => format!("● {}", editor.title()),
+                                false => editor.title(),
+                            };
+                            if ui.selectable_label(i == self.active_editor, title).clicked() {
+                                self.active_editor = i;
+                            }
+                            if ui.small_button("✖").on_hover_text("Close image").clicked() {
+                                close = Some(editor.id());
+                            }
+                        });
+                    }
+                    
+                    ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                        let paste = ui.add_enabled(self.copied_recipe.is_some(), egui::Button::new("Paste recipe"))
+                            .on_hover_text("Apply copied modifier settings on this image");
+                        if paste.clicked() {
+                            if let Some(recipe) = &self.copied_recipe {
+                                self.editors[self.active_editor].apply_recipe(recipe);
+                            }
+                        }
+                        if ui.button("Copy recipe").on_hover_text("Copy modifier settings of this image").clicked() {
+                            self.copied_recipe = Some(self.editors[self.active_editor].recipe());
+                        }
+                    });
+                });
+            });
+        
+        if let Some(id) = close {
+            self.guard_action(GuardedAction::CloseTab(id));
+        }
+        if let Some(file_name) = open_recent {
+            self.load_image_from_file_name(&file_name);
+        }
+    }
+    
+    /// list of recently opened files, returns file selected by the user
+    fn recent_files_ui(&self, ui: &mut egui::Ui) -> Option<String> {
+        let mut selected = None;
+        for file_name in self.recent_files.iter() {
+            if ui.button(file_name).clicked() {
+                selected = Some(file_name.clone());
+            }
+        }
+        selected
+    }
+    
+    /// strip of images in directory of active editor with navigation to neighboring images
+    fn filmstrip_ui(&mut self, ctx: &egui::Context) {
+        let editor = &self.editors[self.active_editor];
+        let current = editor.path().to_path_buf();
+        let dir = match editor.dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        
+        // thumbnails are kept while the active image stays in the same directory
+        if !matches!(&self.filmstrip, Some(f) if f.dir() == dir) {
+            self.filmstrip = Some(Filmstrip::for_dir(dir, self.repaint_signal.clone()));
+        }
+        let filmstrip = match &mut self.filmstrip {
+            Some(filmstrip) => filmstrip,
+            None => return,
+        };
+        
+        let mut open = None;
+        egui::TopBottomPanel::bottom("filmstrip")
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("◀").on_hover_text("Previous image").clicked() {
+                        open = filmstrip.neighbor(&current, -1).cloned();
+                    }
+                    if ui.button("▶").on_hover_text("Next image").clicked() {
+                        open = filmstrip.neighbor(&current, 1).cloned();
+                    }
+                    ui.checkbox(&mut self.carry_recipe, "Carry settings")
+                        .on_hover_text("Apply modifier settings of current image on the next one");
+                });
+                
+                if let FilmstripAction::Open(path) = filmstrip.ui(ui, &current) {
+                    open = Some(path);
+                }
             });
+        
+        if let Some(path) = open {
+            self.open_in_active_tab(&path.display().to_string());
+        }
+    }
+    
+    /// opens image which is `offset` positions away from the active one in the filmstrip
+    fn open_neighbor(&mut self, offset: isize) {
+        let neighbor = match (&self.filmstrip, self.editors.get(self.active_editor)) {
+            (Some(filmstrip), Some(editor)) => filmstrip.neighbor(editor.path(), offset).cloned(),
+            _ => None,
+        };
+        if let Some(path) = neighbor {
+            self.open_in_active_tab(&path.display().to_string());
+        }
+    }
+    
+    /// list of all keyboard shortcuts
+    fn cheat_sheet_ui(&mut self, ctx: &egui::Context) {
+        let keymap = &self.keymap;
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_cheat_sheet)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::Grid::new("cheat_sheet_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            ui.label(action.description());
+                            match keymap.shortcut(action) {
+                                Some(shortcut) => ui.monospace(shortcut.to_string()),
+                                None => ui.weak("not assigned"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                if let Some(path) = keymap.path() {
+                    ui.add_space(10.0);
+                    ui.label(format!("Shortcuts can be changed in {}", path.display()));
+                }
+            });
+    }
+    
+    /// progress overlay of image being loaded
+    fn loading_ui(&mut self, ctx: &egui::Context) {
+        let loader = match &self.loader {
+            Some(loader) => loader,
+            None => return,
+        };
+        
+        let mut cancel = false;
+        egui::Window::new("Loading image")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(loader.file_name());
+                ui.add(egui::ProgressBar::new(loader.progress()).text(loader.stage()).animate(true));
+                ui.vertical_centered(|ui| cancel = ui.button("Cancel").clicked());
+            });
+        
+        if cancel {
+            self.cancel_loading();
         }
     }
      
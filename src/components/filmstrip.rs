@@ -0,0 +1,132 @@
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc::{self, Receiver, Sender}},
+    thread,
+};
+
+use epi::backend::RepaintSignal;
+
+use crate::{
+    constants::FILMSTRIP_THUMBNAIL_SIZE,
+    data::MultimediaFile,
+    utils::{list_supported_files, fit_into},
+};
+
+use super::Image;
+
+pub enum FilmstripAction {
+    Nothing,
+    /// user selected another image of the directory
+    Open(PathBuf),
+}
+
+/// Strip of thumbnails of all supported images in a directory.
+///   Thumbnails are generated on a background thread and cached for the lifetime of the strip.
+pub struct Filmstrip {
+    dir: PathBuf,
+    files: Vec<PathBuf>,
+    thumbnails: HashMap<PathBuf, Image>,
+    receiver: Receiver<(PathBuf, Image)>,
+}
+
+// constructors
+impl Filmstrip {
+    
+    pub fn for_dir(dir: PathBuf, repaint_signal: Arc<dyn RepaintSignal>) -> Self {
+        let files = list_supported_files(&dir);
+        let (sender, receiver) = mpsc::channel();
+        
+        let queue = files.clone();
+        thread::spawn(move || generate_thumbnails(queue, sender, repaint_signal));
+        
+        Self {
+            dir,
+            files,
+            thumbnails: HashMap::new(),
+            receiver,
+        }
+    }
+}
+
+// properties
+impl Filmstrip {
+    
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+    
+    /// Returns file which is `offset` positions away from the current one in the strip
+    pub fn neighbor(&self, current: &Path, offset: isize) -> Option<&PathBuf> {
+        let index = self.files.iter().position(|f| f == current)? as isize + offset;
+        if index < 0 {
+            return None;
+        }
+        self.files.get(index as usize)
+    }
+}
+
+// ui code
+impl Filmstrip {
+    
+    pub fn ui(&mut self, ui: &mut egui::Ui, current: &Path) -> FilmstripAction {
+        // collect thumbnails generated since last frame
+        while let Ok((path, thumbnail)) = self.receiver.try_recv() {
+            self.thumbnails.insert(path, thumbnail);
+        }
+        
+        let mut action = FilmstripAction::Nothing;
+        let size = egui::Vec2::splat(FILMSTRIP_THUMBNAIL_SIZE);
+        
+        egui::ScrollArea::horizontal()
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for file in self.files.iter() {
+                        let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+                        let selected = file == current;
+                        
+                        let response = match self.thumbnails.get_mut(file) {
+                            Some(thumbnail) => {
+                                let texture = thumbnail.texture(ui.ctx()).id();
+                                let thumbnail_size = fit_into(&size, &thumbnail.size_vec2(), true);
+                                ui.add(egui::ImageButton::new(texture, thumbnail_size).selected(selected))
+                            },
+                            // thumbnail is not generated yet
+                            None => ui.add_sized(size, egui::SelectableLabel::new(selected, "…")),
+                        };
+                        
+                        if selected {
+                            response.scroll_to_me(None);
+                        }
+                        if response.on_hover_text(name).clicked() && !selected {
+                            action = FilmstripAction::Open(file.clone());
+                        }
+                    }
+                });
+            });
+        
+        action
+    }
+}
+
+/// Body of thumbnail generating thread, stops when the strip is dropped
+fn generate_thumbnails(files: Vec<PathBuf>, sender: Sender<(PathBuf, Image)>, repaint_signal: Arc<dyn RepaintSignal>) {
+    let size = FILMSTRIP_THUMBNAIL_SIZE as u32;
+    for path in files {
+        let thumbnail = MultimediaFile::from_file(&path)
+            .ok()
+            .and_then(|mut file| {
+                let bytes = file.bytes().ok()?;
+                let image = Image::from_bytes(&bytes).ok()?;
+                Some(image.thumbnail(size, size).into_oriented(file.metadata().orientation()))
+            });
+        
+        if let Some(thumbnail) = thumbnail {
+            if sender.send((path, thumbnail)).is_err() {
+                return;
+            }
+            repaint_signal.request_repaint();
+        }
+    }
+}
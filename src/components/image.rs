@@ -1,12 +1,44 @@
 
 use std::fmt::Debug;
 
-use image::DynamicImage;
+use egui::{Color32, Rect, Shape};
+use image::{DynamicImage, Rgba, ColorType, ImageFormat, GenericImageView};
 use image::imageops::FilterType;
 use image::imageops::colorops::{brighten_in_place, contrast_in_place};
 
+use lcms2::{Profile, ColorSpaceSignature};
+
+use crate::utils::color::{srgb_to_linear, linear_to_srgb};
+use crate::data::{ColorProfile, transform_pixels};
+
+/// Size of one square of checkerboard drawn behind transparent images
+const CHECKERBOARD_SQUARE: f32 = 8.0;
+const CHECKERBOARD_LIGHT: Color32 = Color32::from_gray(204);
+const CHECKERBOARD_DARK: Color32 = Color32::from_gray(153);
+
+/// Encoding of color values stored in an image
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    /// Values are proportional to the light intensity
+    Linear,
+    /// Values are gamma encoded sRGB as they are stored in common image files
+    Perceptual,
+}
+
+/// Image being edited.
+///   Alpha is always stored straight (not premultiplied), operations mixing neighboring pixels
+///   premultiply colors for their duration so transparent pixels do not bleed into visible ones.
 pub struct Image {
     pub raw_image: DynamicImage,
+    color_space: ColorSpace,
+    
+    /// Pixel format of the decoded file, used to save result in the same precision
+    source_color: ColorType,
+    
+    /// Width of this image relative to the original it was resampled from.
+    ///   Filters with radius given in pixels of the original multiply it by this value.
+    scale: f32,
+    
     texture: Option<egui::TextureHandle>,
 }
 
@@ -27,6 +59,8 @@ impl Debug for Image {
         
         f.debug_struct("Image")
             .field("raw_image", &format!("{} x {} - {}", i_size.x, i_size.y, self.raw_size()))
+            .field("color_space", &self.color_space)
+            .field("source_color", &self.source_color)
             .field("texture", &a)
             .finish()
     }
@@ -34,7 +68,13 @@ impl Debug for Image {
 
 impl Clone for Image {
     fn clone(&self) -> Self {
-        Self { raw_image: self.raw_image.clone(), texture: None }
+        Self {
+            raw_image: self.raw_image.clone(),
+            color_space: self.color_space,
+            source_color: self.source_color,
+            scale: self.scale,
+            texture: None
+        }
     }
 }
 
@@ -43,7 +83,10 @@ impl Image {
     
     pub fn from_image(image: DynamicImage) -> Self {
         Self {
+            source_color: image.color(),
             raw_image: image,
+            color_space: ColorSpace::Perceptual,
+            scale: 1.0,
             texture: None
         }
     }
@@ -62,13 +105,83 @@ impl Image {
         [self.raw_image.width() as f32, self.raw_image.height() as f32].into()
     }
     
+    /// Width of this image relative to the original image, 1.0 for images which were not resampled
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+    
     pub fn raw_size(&self) -> u64 {
         self.raw_image.as_bytes().len() as _
     }
     
+    /// Perceptually encoded color of a pixel with channels in range 0.0 - 1.0
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba<f32>> {
+        if x >= self.raw_image.width() || y >= self.raw_image.height() {
+            return None;
+        }
+        
+        let mut pixel = match &self.raw_image {
+            DynamicImage::ImageRgba32F(image) => *image.get_pixel(x, y),
+            image => Rgba(image.get_pixel(x, y).0.map(|c| c as f32 / 255.0)),
+        };
+        if self.color_space == ColorSpace::Linear {
+            for i in 0..3 {
+                pixel[i] = linear_to_srgb(pixel[i]);
+            }
+        }
+        Some(pixel)
+    }
+    
+    /// Copy of all pixels perceptually encoded with channels in range 0.0 - 1.0
+    pub fn perceptual_pixels(&self) -> image::Rgba32FImage {
+        let mut image = self.raw_image.to_rgba32f();
+        if self.color_space == ColorSpace::Linear {
+            convert_pixels(&mut image, linear_to_srgb);
+        }
+        image
+    }
+    
+    /// Whether the source image has alpha channel
+    pub fn has_alpha(&self) -> bool {
+        self.source_color.has_alpha()
+    }
+    
+    /// Number of bits per channel of the source image
+    pub fn bit_depth(&self) -> u16 {
+        self.source_color.bits_per_pixel() / self.source_color.channel_count() as u16
+    }
+    
+    /// Returns perceptually encoded copy of raw image suitable for saving into a file of given format.
+    ///   Colors are converted into the output profile and precision of the source image is kept when the format supports it.
+    pub fn to_output_image(&self, format: ImageFormat, profile: ColorProfile) -> Result<DynamicImage, String> {
+        let image = self.clone().into_color_space(ColorSpace::Perceptual).into_profile(profile)?.raw_image;
+        
+        // jpeg supports only 8 bit channels without alpha
+        let is_jpeg = format == ImageFormat::Jpeg;
+        let high_precision = self.bit_depth() > 8 && !is_jpeg;
+        let alpha = self.source_color.has_alpha() && !is_jpeg;
+        
+        Ok(match (high_precision, alpha) {
+            (true, true) => DynamicImage::ImageRgba16(image.to_rgba16()),
+            (true, false) => DynamicImage::ImageRgb16(image.to_rgb16()),
+            (false, true) => DynamicImage::ImageRgba8(image.to_rgba8()),
+            (false, false) => DynamicImage::ImageRgb8(image.to_rgb8()),
+        })
+    }
+    
     pub fn texture(&mut self, ctx: &egui::Context) -> &egui::TextureHandle {
+        let color_space = self.color_space;
+        let raw_image = &self.raw_image;
         self.texture.get_or_insert_with(|| {
-            let image = self.raw_image.to_rgba8();
+            // texture is always rendered in perceptual space
+            let image = match color_space {
+                ColorSpace::Perceptual => raw_image.to_rgba8(),
+                ColorSpace::Linear => {
+                    let mut image = raw_image.to_rgba32f();
+                    convert_pixels(&mut image, linear_to_srgb);
+                    DynamicImage::ImageRgba32F(image).to_rgba8()
+                },
+            };
             let pixels = image.as_flat_samples();
             let image = egui::ColorImage::from_rgba_unmultiplied(
                 [image.width() as _, image.height() as _],
@@ -81,24 +194,193 @@ impl Image {
 
 // operations
 impl Image {
+    
+    /// Marks opaque image as having alpha channel in the same precision, so its alpha is kept when saved
+    pub fn add_alpha_channel(&mut self) {
+        self.source_color = match self.source_color {
+            ColorType::L8 => ColorType::La8,
+            ColorType::L16 => ColorType::La16,
+            ColorType::Rgb8 => ColorType::Rgba8,
+            ColorType::Rgb16 => ColorType::Rgba16,
+            ColorType::Rgb32F => ColorType::Rgba32F,
+            color => color,
+        };
+    }
+    
     pub fn resize(&self, size: egui::Vec2, filter: FilterType) -> Image {
-        Image {
-            raw_image: self.raw_image.resize(
-                size.x as u32,
-                size.y as u32,
-                filter
-            ),
-            texture: None,
-        }
+        self.resampled(|image| image.resize(
+            size.x as u32,
+            size.y as u32,
+            filter
+        ))
+    }
+    
+    /// Rotates and flips the image according to EXIF orientation value so it is stored upright.
+    ///   See: https://www.impulseadventure.com/photo/exif-orientation.html
+    pub fn into_oriented(mut self, orientation: u32) -> Image {
+        let image = &self.raw_image;
+        self.raw_image = match orientation {
+            2 => image.fliph(),
+            3 => image.rotate180(),
+            4 => image.flipv(),
+            5 => image.rotate90().fliph(),
+            6 => image.rotate90(),
+            7 => image.rotate270().fliph(),
+            8 => image.rotate270(),
+            _ => return self,
+        };
+        self.texture = None;
+        self
     }
     
     pub fn thumbnail(&self, w: u32, h: u32) -> Image {
-        Image {
-            raw_image: self.raw_image.thumbnail(w, h),
-            texture: None,
+        self.resampled(|image| image.thumbnail(w, h))
+    }
+    
+    /// Creates new image by resampling this one. Opaque image is resampled directly without copying,
+    ///   transparent one is copied once with premultiplied colors.
+    fn resampled(&self, operation: impl FnOnce(&DynamicImage) -> DynamicImage) -> Image {
+        let raw_image = if self.has_alpha() {
+            let mut premultiplied = self.raw_image.to_rgba32f();
+            premultiplied.pixels_mut().for_each(premultiply);
+            let mut image = operation(&DynamicImage::ImageRgba32F(premultiplied)).into_rgba32f();
+            image.pixels_mut().for_each(unpremultiply);
+            DynamicImage::ImageRgba32F(image)
+        } else {
+            operation(&self.raw_image)
+        };
+        
+        Self {
+            scale: self.scale * raw_image.width() as f32 / self.raw_image.width().max(1) as f32,
+            raw_image,
+            color_space: self.color_space,
+            source_color: self.source_color,
+            texture: None
         }
     }
     
+    /// Returns image with values encoded in desired color space.
+    ///   Converted image is stored in 32 bit float precision so no information is lost by the conversion.
+    pub fn into_color_space(mut self, color_space: ColorSpace) -> Image {
+        if self.color_space == color_space {
+            return self;
+        }
+        
+        let transfer = match color_space {
+            ColorSpace::Linear => srgb_to_linear,
+            ColorSpace::Perceptual => linear_to_srgb,
+        };
+        
+        let mut image = self.raw_image.into_rgba32f();
+        convert_pixels(&mut image, transfer);
+        
+        self.raw_image = DynamicImage::ImageRgba32F(image);
+        self.color_space = color_space;
+        self.texture = None;
+        self
+    }
+    
+    /// Converts colors described by embedded ICC profile into sRGB working space.
+    ///   Image without profile or with unsupported profile is treated as sRGB already,
+    ///   the returned warning tells the user why the embedded profile was not used.
+    pub fn into_working_space(mut self, icc: Option<&[u8]>) -> (Image, Option<String>) {
+        let source = match icc.map(Profile::new_icc) {
+            None => return (self, None),
+            Some(Ok(profile)) if profile.color_space() == ColorSpaceSignature::RgbData => profile,
+            Some(Ok(profile)) => {
+                let warning = format!("Embedded {:?} color profile is not supported, colors are treated as sRGB", profile.color_space());
+                return (self, Some(warning));
+            },
+            Some(Err(err)) => {
+                let warning = format!("Embedded color profile cannot be read ({err}), colors are treated as sRGB");
+                return (self, Some(warning));
+            },
+        };
+        
+        let mut image = self.raw_image.to_rgba32f();
+        if let Err(err) = transform_pixels(&mut image, &source, &ColorProfile::Srgb.profile()) {
+            let warning = format!("Colors cannot be converted from embedded profile ({err}), they are treated as sRGB");
+            return (self, Some(warning));
+        }
+        self.raw_image = DynamicImage::ImageRgba32F(image);
+        self.texture = None;
+        (self, None)
+    }
+    
+    /// Converts perceptually encoded sRGB colors into given output profile
+    fn into_profile(mut self, profile: ColorProfile) -> Result<Image, String> {
+        if profile != ColorProfile::Srgb {
+            let mut image = self.raw_image.into_rgba32f();
+            transform_pixels(&mut image, &ColorProfile::Srgb.profile(), &profile.profile())?;
+            self.raw_image = DynamicImage::ImageRgba32F(image);
+            self.texture = None;
+        }
+        Ok(self)
+    }
+    
+    /// Returns image as it will look like when saved in given output profile and displayed on sRGB screen.
+    ///   Colors are converted into the profile, clipped and quantized as in 8 bit file and converted back.
+    pub fn soft_proofed(&self, profile: ColorProfile) -> Result<Image, String> {
+        let mut proof = self.clone().into_color_space(ColorSpace::Perceptual).into_profile(profile)?;
+        let mut image = DynamicImage::ImageRgba8(proof.raw_image.to_rgba8()).into_rgba32f();
+        transform_pixels(&mut image, &profile.profile(), &ColorProfile::Srgb.profile())?;
+        proof.raw_image = DynamicImage::ImageRgba32F(image);
+        Ok(proof)
+    }
+    
+    /// Composites the image over opaque background color given in linear light.
+    ///   Resulting image has no alpha channel and is saved without it.
+    pub fn flattened(self, background: [f32; 3]) -> Image {
+        let color_space = self.color_space;
+        let mut image = self.into_color_space(ColorSpace::Linear);
+        image.map_pixels(|p| {
+            for i in 0..3 {
+                p[i] = p[i] * p[3] + background[i] * (1.0 - p[3]);
+            }
+            p[3] = 1.0;
+        });
+        image.source_color = if image.bit_depth() > 8 { ColorType::Rgb16 } else { ColorType::Rgb8 };
+        image.into_color_space(color_space)
+    }
+    
+    /// Calls `func` on every pixel with channels normalized into 0.0 - 1.0 range.
+    ///   Raw image is converted into 32 bit float buffer if it is not already in this format.
+    pub fn map_pixels(&mut self, func: impl Fn(&mut Rgba<f32>)) {
+        if !matches!(self.raw_image, DynamicImage::ImageRgba32F(_)) {
+            self.raw_image = DynamicImage::ImageRgba32F(self.raw_image.to_rgba32f());
+        }
+        
+        if let DynamicImage::ImageRgba32F(image) = &mut self.raw_image {
+            image.pixels_mut().for_each(func);
+        }
+        self.texture = None;
+    }
+    
+    /// Replaces pixels by result of `operation` which reads the whole 32 bit float buffer, used by filters sampling neighbors.
+    ///   Values stay in the current color space of the image.
+    pub fn map_buffer(&mut self, operation: impl FnOnce(&image::Rgba32FImage) -> image::Rgba32FImage) {
+        if !matches!(self.raw_image, DynamicImage::ImageRgba32F(_)) {
+            self.raw_image = DynamicImage::ImageRgba32F(self.raw_image.to_rgba32f());
+        }
+        
+        if let DynamicImage::ImageRgba32F(image) = &self.raw_image {
+            self.raw_image = DynamicImage::ImageRgba32F(operation(image));
+        }
+        self.texture = None;
+    }
+    
+    /// Same as `map_buffer` but colors passed to the operation are premultiplied by alpha,
+    ///   so hidden colors of transparent pixels do not leak into neighbors. Opaque images are passed directly.
+    pub fn map_buffer_premultiplied(&mut self, operation: impl FnOnce(&image::Rgba32FImage) -> image::Rgba32FImage) {
+        if !self.has_alpha() {
+            return self.map_buffer(operation);
+        }
+        
+        self.map_pixels(premultiply);
+        self.map_buffer(operation);
+        self.map_pixels(unpremultiply);
+    }
+    
     pub fn brighten_in_place(&mut self, level: f32) {
         let level = level.clamp(-100.0, 100.0) * 2.0;
         brighten_in_place(&mut self.raw_image, level as i32);
@@ -110,6 +392,31 @@ impl Image {
     }
 }
 
+/// multiplies color channels by alpha
+fn premultiply(pixel: &mut Rgba<f32>) {
+    for i in 0..3 {
+        pixel[i] *= pixel[3];
+    }
+}
+
+/// divides premultiplied color channels by alpha, filters may overshoot so colors are limited to full intensity
+fn unpremultiply(pixel: &mut Rgba<f32>) {
+    if pixel[3] > 0.0 {
+        for i in 0..3 {
+            pixel[i] = (pixel[i] / pixel[3]).min(1.0);
+        }
+    }
+}
+
+/// applies transfer function on color channels of every pixel leaving alpha untouched
+fn convert_pixels(image: &mut image::Rgba32FImage, transfer: fn(f32) -> f32) {
+    for pixel in image.pixels_mut() {
+        for i in 0..3 {
+            pixel[i] = transfer(pixel[i]);
+        }
+    }
+}
+
 // ui rendering
 impl Image {
     pub fn show(&mut self, ui: &mut egui::Ui) -> egui::Response {
@@ -119,4 +426,31 @@ impl Image {
     pub fn show_sized(&mut self, ui: &mut egui::Ui, desired_size: egui::Vec2) -> egui::Response {
         ui.image(self.texture(ui.ctx()).id(), desired_size)
     }
+    
+    /// Shows the image over checkerboard pattern so transparent areas are distinguishable from the background
+    pub fn show_over_checkerboard(&mut self, ui: &mut egui::Ui, desired_size: egui::Vec2) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
+        if ui.is_rect_visible(rect) {
+            if self.has_alpha() {
+                ui.painter().extend(checkerboard(rect));
+            }
+            egui::Image::new(self.texture(ui.ctx()).id(), desired_size).paint_at(ui, rect);
+        }
+        response
+    }
+}
+
+fn checkerboard(rect: Rect) -> Vec<Shape> {
+    let columns = (rect.width() / CHECKERBOARD_SQUARE).ceil() as usize;
+    let rows = (rect.height() / CHECKERBOARD_SQUARE).ceil() as usize;
+    
+    let mut shapes = vec![Shape::rect_filled(rect, 0.0, CHECKERBOARD_LIGHT)];
+    for row in 0..rows {
+        for column in (row % 2..columns).step_by(2) {
+            let min = rect.min + egui::vec2(column as f32, row as f32) * CHECKERBOARD_SQUARE;
+            let square = Rect::from_min_size(min, egui::Vec2::splat(CHECKERBOARD_SQUARE)).intersect(rect);
+            shapes.push(Shape::rect_filled(square, 0.0, CHECKERBOARD_DARK));
+        }
+    }
+    shapes
 }
@@ -1,28 +1,41 @@
 
-use std::sync::Arc;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use egui::{style::Margin, Vec2};
-use epi::backend::RepaintSignal;
+use image::ImageFormat;
 use indoc::indoc;
 
 use crate::{
-    widgets::{texts, BigButton},
-    utils::{save_output_file, fit_into, format_size},
-    data::{MultimediaFile, Viewport},
-    constants::{RIGHT_PANEL_WIDTH, THUMBNAIL_SIZE}
+    widgets::{texts, BigButton, Toasts},
+    utils::{save_output_file, save_lut_file, fit_into, format_size},
+    data::{MultimediaFile, Viewport, MetadataExport, ColorProfile, embed_profile, Action},
+    constants::{RIGHT_PANEL_WIDTH, THUMBNAIL_SIZE, ZOOM_STEP, MIN_ZOOM_LEVEL, MAX_ZOOM_LEVEL, MAX_HISTORY, LUT_EXPORT_SIZES}
 };
 
 use super::{
     ModifierPipeline,
+    ClippingOverlay,
+    PixelInspector,
+    Scopes,
+    Recipe,
+    DocumentSession,
     Image,
+    ColorSpace,
     modifiers::{
         Slider,
+        ModifierResponse,
         specific::{
             ExposureModifier,
             ContrastModifier,
             BlurModifier,
             TintModifier,
             CustomModifier,
+            LutModifier,
+            ConvolutionModifier,
+            DenoiseModifier,
+            MedianModifier,
+            ShadowsHighlightsModifier,
             BMulModifier,
             BMulLumaModifier,
             GammaModifier
@@ -35,37 +48,75 @@ pub enum EditorResult {
     LoadNewImage,
 }
 
+/// Source of editor ids, ids are unique for the whole run of the application
+static NEXT_EDITOR_ID: AtomicU64 = AtomicU64::new(0);
+
 pub struct ImageEditor {
+    /// identifies the editor while tabs are opened and closed
+    id: u64,
     pipeline: ModifierPipeline,
     media_file: MultimediaFile,
     viewport: Viewport,
+    metadata_export: MetadataExport,
+    output_profile: ColorProfile,
+    soft_proof: bool,
+    
+    /// when set then clipped highlights and shadows are marked in the viewport
+    show_clipping: bool,
+    clipping: ClippingOverlay,
+    
+    /// edge size of exported 3D LUT
+    lut_size: usize,
+    
+    /// when set then transparent image is composited over background color on save
+    flatten: bool,
+    /// background color for flattening in linear light
+    background: [f32; 3],
+    
+    /// image was modified since it was loaded or saved
+    dirty: bool,
+    /// file the image was last saved into, next save writes there without asking
+    save_path: Option<std::path::PathBuf>,
+    
+    /// recipes of previous states for undo, the latest is last
+    history: Vec<Recipe>,
+    /// recipes of undone states for redo, the latest undone is last
+    future: Vec<Recipe>,
+    /// state recorded in history as current
+    committed_recipe: Recipe,
+    /// modifiers changed since last commit into history, changes are committed when mouse is released
+    uncommitted: bool,
+    /// settings of freshly loaded image used to reset all modifiers
+    initial_recipe: Recipe,
+    
+    /// original image is shown instead of result
+    compare: bool,
+    /// original image is shown while the shortcut is held
+    hold_original: bool,
+    /// scroll offset of the viewport has to be set from restored session
+    restore_offset: bool,
+    
+    inspector: PixelInspector,
+    scopes: Scopes,
+    
+    /// problem found while loading the file, reported to the user when the editor is shown
+    load_warning: Option<String>,
 }
 
 // constructors
 impl ImageEditor {
     
-    pub fn from_file(mut media_file: MultimediaFile, repaint_signal: Arc<dyn RepaintSignal>) -> Result<Self, String> {
-        match Image::from_bytes(media_file.bytes().as_slice()) {
+    /// Reads and decodes image of the file, the image is rotated upright and converted into linear working space.
+    ///   Returns the image with warning about color profile which could not be used.
+    pub fn decode_file(media_file: &mut MultimediaFile) -> Result<(Image, Option<String>), String> {
+        let bytes = media_file.bytes().map_err(|err| err.to_string())?;
+        match Image::from_bytes(bytes.as_slice()) {
             Ok(original_image) => {
-                let viewport = Viewport::new().sized(original_image.size_vec2());
-                let preview_size = viewport.size;
-                let mut pipeline = ModifierPipeline::new(original_image, preview_size);
-                
-                let tm = pipeline.original_image().thumbnail(THUMBNAIL_SIZE as u32, THUMBNAIL_SIZE as u32);
-                pipeline.push_modifier(Box::new(ExposureModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(GammaModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(BMulLumaModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(BMulModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(ContrastModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(BlurModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(TintModifier::with_thumbnails(&tm)));
-                pipeline.push_modifier(Box::new(CustomModifier::with_thumbnails(&tm)));
-                
-                Ok(Self {
-                    media_file,
-                    viewport,
-                    pipeline,
-                })
+                let metadata = media_file.metadata();
+                let (image, warning) = original_image
+                    .into_oriented(metadata.orientation())
+                    .into_working_space(metadata.icc_profile());
+                Ok((image.into_color_space(ColorSpace::Linear), warning))
             },
             
             Err(message) => Err(format!(indoc!("
@@ -77,34 +128,281 @@ impl ImageEditor {
         }
     }
     
+    pub fn new(media_file: MultimediaFile, original_image: Image) -> Self {
+        let viewport = Viewport::new().sized(original_image.size_vec2());
+        let preview_size = viewport.size;
+        let mut pipeline = ModifierPipeline::new(original_image, preview_size);
+        
+        let tm = pipeline.original_image().thumbnail(THUMBNAIL_SIZE as u32, THUMBNAIL_SIZE as u32);
+        pipeline.push_modifier(Box::new(MedianModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(DenoiseModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(ExposureModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(GammaModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(ShadowsHighlightsModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(BMulLumaModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(BMulModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(ContrastModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(BlurModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(ConvolutionModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(TintModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(CustomModifier::with_thumbnails(&tm)));
+        pipeline.push_modifier(Box::new(LutModifier::with_thumbnails(&tm)));
+        let initial_recipe = pipeline.recipe();
+        
+        Self {
+            id: NEXT_EDITOR_ID.fetch_add(1, Ordering::Relaxed),
+            media_file,
+            viewport,
+            pipeline,
+            metadata_export: MetadataExport::Keep,
+            output_profile: ColorProfile::Srgb,
+            soft_proof: false,
+            show_clipping: false,
+            clipping: ClippingOverlay::default(),
+            lut_size: 33,
+            flatten: false,
+            background: [1.0, 1.0, 1.0],
+            dirty: false,
+            save_path: None,
+            history: vec![],
+            future: vec![],
+            committed_recipe: initial_recipe.clone(),
+            uncommitted: false,
+            initial_recipe,
+            compare: false,
+            hold_original: false,
+            restore_offset: false,
+            inspector: PixelInspector::default(),
+            scopes: Scopes::default(),
+            load_warning: None,
+        }
+    }
+    
+    pub fn with_load_warning(mut self, warning: Option<String>) -> Self {
+        self.load_warning = warning;
+        self
+    }
+    
 }
 
 
+// non-mutable methods
+impl ImageEditor {
+    
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    
+    /// Name of the edited file without directory
+    pub fn title(&self) -> String {
+        self.media_file
+            .get_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.media_file.file_name_owned())
+    }
+    
+    pub fn path(&self) -> &std::path::Path {
+        self.media_file.get_path()
+    }
+    
+    /// Directory of the edited file
+    pub fn dir(&self) -> Option<std::path::PathBuf> {
+        self.media_file.get_dir()
+    }
+    
+    /// Whether there are changes which were not saved
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    
+    pub fn recipe(&self) -> Recipe {
+        self.pipeline.recipe()
+    }
+    
+    /// State of the editor to be restored in next run of the application
+    pub fn session(&self) -> DocumentSession {
+        DocumentSession {
+            path: self.path().display().to_string(),
+            recipe: self.recipe(),
+            zoom_level: self.viewport.zoom_level,
+            offset: self.viewport.offset,
+        }
+    }
+}
+
 // mutating methods
 impl ImageEditor {
     
-    pub fn size_viewport(&mut self, size: Vec2) {
+    pub fn apply_recipe(&mut self, recipe: &Recipe) {
+        self.pipeline.apply_recipe(recipe);
+        self.dirty = true;
+        self.commit_history();
+    }
+    
+    /// Restores modifiers and viewport stored in previous run of the application.
+    ///   Restored state is the starting point of the editor, so it is neither dirty nor recorded in history.
+    pub fn restore(&mut self, session: &DocumentSession) {
+        self.pipeline.apply_recipe(&session.recipe);
+        self.committed_recipe = self.recipe();
+        self.viewport.zoom_level = session.zoom_level;
+        self.viewport.offset = session.offset;
+        self.restore_offset = true;
+    }
+    
+    /// Records current state of modifiers into undo history
+    fn commit_history(&mut self) {
+        self.uncommitted = false;
+        let recipe = self.recipe();
+        if recipe != self.committed_recipe {
+            self.history.push(std::mem::replace(&mut self.committed_recipe, recipe));
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.future.clear();
+        }
+    }
+    
+    pub fn undo(&mut self) {
+        self.commit_history();
+        if let Some(recipe) = self.history.pop() {
+            self.pipeline.apply_recipe(&recipe);
+            self.future.push(std::mem::replace(&mut self.committed_recipe, recipe));
+            self.dirty = true;
+        }
+    }
+    
+    pub fn redo(&mut self) {
+        self.commit_history();
+        if let Some(recipe) = self.future.pop() {
+            self.pipeline.apply_recipe(&recipe);
+            self.history.push(std::mem::replace(&mut self.committed_recipe, recipe));
+            self.dirty = true;
+        }
+    }
+    
+    /// Sets all modifiers to their state right after loading
+    pub fn reset_all(&mut self) {
+        let initial = self.initial_recipe.clone();
+        self.apply_recipe(&initial);
+    }
+    
+    pub fn zoom(&mut self, steps: f32) {
+        self.viewport.zoom_level = (self.viewport.zoom_level + steps).clamp(MIN_ZOOM_LEVEL, MAX_ZOOM_LEVEL);
+    }
+    
+    pub fn zoom_to_fit(&mut self) {
+        self.viewport.zoom_level = 0.0;
+        self.viewport.offset = Vec2::ZERO;
+    }
+    
+    /// Shows original image instead of result while set
+    pub fn set_hold_original(&mut self, hold: bool) {
+        self.hold_original = hold;
+    }
+    
+    /// Performs action triggered by keyboard shortcut
+    pub fn perform(&mut self, action: Action, toasts: &mut Toasts) {
+        match action {
+            Action::Save => self.save_and_notify(toasts, false),
+            Action::Export => self.save_and_notify(toasts, true),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::ZoomIn => self.zoom(ZOOM_STEP),
+            Action::ZoomOut => self.zoom(-ZOOM_STEP),
+            Action::ZoomFit => self.zoom_to_fit(),
+            Action::ToggleCompare => self.compare = !self.compare,
+            Action::ResetAll => self.reset_all(),
+            _ => {},
+        }
+    }
+    
+    /// Sizes viewport and returns size in which the image is displayed with current zoom.
+    ///   Pipeline is evaluated in the displayed size up to size of the original.
+    pub fn size_viewport(&mut self, size: Vec2) -> Vec2 {
+        self.viewport.size = size;
+        let original_size = self.pipeline.original_image().size_vec2();
+        let display_size = fit_into(&self.viewport.size, &original_size, false) * self.viewport.scale();
         
-        if *self.pipeline.preview_size() != size {
-            self.viewport.size = size;
-            self.pipeline.resize(fit_into(
-                &self.viewport.size,
-                &self.pipeline.original_image().size_vec2(),
-                false
-            ));
+        let preview_size = display_size.min(original_size);
+        if *self.pipeline.preview_size() != preview_size {
+            self.pipeline.resize(preview_size);
         }
+        display_size
     }
     
-    fn save_image_to_file(&self) {
-        if let Some(p) = save_output_file(Some(&self.media_file)) {
-            self.pipeline.apply_to_original().raw_image.save(p);
+    /// Saves edited image into file it was last saved into or asks for output file.
+    ///   Returns path of saved file or `None` when the user cancelled the dialog.
+    pub fn save(&mut self, ask_path: bool) -> Result<Option<std::path::PathBuf>, String> {
+        let path = match &self.save_path {
+            Some(path) if !ask_path => Some(path.clone()),
+            _ => save_output_file(Some(&self.media_file)),
+        };
+        
+        if let Some(p) = path {
+            let format = ImageFormat::from_path(&p).unwrap_or(ImageFormat::Png);
+            let mut image = self.pipeline.apply_to_original();
+            
+            // jpeg can not store alpha, so transparent images are always flattened into it
+            if image.has_alpha() && (self.flatten || format == ImageFormat::Jpeg) {
+                image = image.flattened(self.background);
+            }
+            let image = image.to_output_image(format, self.output_profile)?;
+            
+            // image is encoded into memory first so metadata and color profile can be inserted into the file
+            let mut encoded = Cursor::new(vec![]);
+            image.write_to(&mut encoded, format).map_err(|err| err.to_string())?;
+            let mut bytes = self.media_file.metadata().embed(encoded.into_inner(), self.metadata_export)?;
+            if let Some(icc) = self.output_profile.icc() {
+                bytes = embed_profile(bytes, &icc)?;
+            }
+            std::fs::write(&p, bytes).map_err(|err| err.to_string())?;
+            self.dirty = false;
+            self.save_path = Some(p.clone());
+            return Ok(Some(p));
+        }
+        Ok(None)
+    }
+    
+    /// Asks for a path and writes pointwise modifiers baked into `.cube` LUT.
+    ///   Returns written path with titles of skipped spatial modifiers or `None` when the dialog was cancelled.
+    pub fn export_lut(&self) -> Result<Option<(std::path::PathBuf, Vec<String>)>, String> {
+        let path = match save_lut_file(&self.media_file) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        
+        let title = path.file_stem().map(|s| s.to_string_lossy().to_string());
+        let (lut, skipped) = self.pipeline.export_lut(self.lut_size, title);
+        lut.save(&path)?;
+        Ok(Some((path, skipped)))
+    }
+    
+    /// Saves the image and reports the result to the user
+    pub fn save_and_notify(&mut self, toasts: &mut Toasts, ask_path: bool) {
+        match self.save(ask_path) {
+            Ok(Some(path)) => {
+                toasts.info(format!("Saved to {}", path.display()));
+                
+                // only jpeg and png containers are supported by the embedding, every output profile is embedded
+                let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+                let has_metadata = !self.media_file.metadata().is_empty() && self.metadata_export != MetadataExport::StripAll;
+                if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png) {
+                    toasts.warning(match has_metadata {
+                        true => "Metadata and color profile were not written, they are supported only in JPEG and PNG files",
+                        false => "Color profile was not written, it is supported only in JPEG and PNG files",
+                    });
+                }
+            },
+            Ok(None) => {},
+            Err(message) => toasts.error(format!("Saving failed: {message}")),
         }
     }
 }
 
 // ui code
 impl ImageEditor {
-    pub fn ui(&mut self, ctx: &egui::Context, frame: &epi::Frame) -> EditorResult {
+    pub fn ui(&mut self, ctx: &egui::Context, frame: &epi::Frame, toasts: &mut Toasts) -> EditorResult {
         
         let mut result = EditorResult::Nothing;
         
@@ -137,12 +435,16 @@ impl ImageEditor {
                             format_size(original.raw_size())
                         ));
                         ui.separator();
+                        ui.label( format!("Bit depth: {} bit", original.bit_depth()));
+                        ui.separator();
                         ui.label( format!(
                             "Current size: {} x {} - {}",
                             current_size.x,
                             current_size.y,
                             format_size(current.raw_size())
                         ));
+                        ui.separator();
+                        self.scopes.toggle_ui(ui);
                     });
                     // if is_resizing  {
                     //     ui.with_layout(egui::Layout::right_to_left().with_cross_justify(true), |ui| {
@@ -165,48 +467,201 @@ impl ImageEditor {
                     result = EditorResult::LoadNewImage;
                 }
                 
+                self.metadata_ui(ui);
+                self.inspector.panel_ui(ui, &self.pipeline);
+                
                 ui.label(texts::sized("Filters: ", 20.0));
                 
-                let h = ui.available_height() - 55.0;
+                let has_alpha = self.pipeline.current_image().has_alpha();
+                let h = ui.available_height()
+                    - if has_alpha { 170.0 } else { 145.0 }
+                    - if self.show_clipping { 25.0 } else { 0.0 };
                 egui::ScrollArea::vertical()
                     .max_height(h)
                     .show(ui, |ui| {
                         // ui.set_height(h);
-                        self.pipeline.ui(ui);
+                        if self.pipeline.ui(ui) == ModifierResponse::Changed {
+                            self.dirty = true;
+                            self.uncommitted = true;
+                        }
                     });
                 
+                if has_alpha {
+                    self.transparency_ui(ui);
+                }
+                self.output_profile_ui(ui);
+                self.clipping_ui(ui);
+                self.lut_export_ui(ui, toasts);
+                
                 if BigButton::ui(ui, "💾  Save").clicked() {
-                   self.save_image_to_file();
+                    self.save_and_notify(toasts, false);
                 }
         });
         
+        self.scopes.ui(ctx, &self.pipeline);
+        
         // image viewport
         egui::CentralPanel::default()
             .show(ctx, |ui| {
-                self.size_viewport(ui.available_size() * 0.98);
+                let display_size = self.size_viewport(ui.available_size() * 0.98);
                 
-                let offset = 0.5 * (ui.available_size() - *self.pipeline.preview_size());
+                // image smaller than the viewport is centered, bigger one is scrolled
+                let offset = (0.5 * (ui.available_size() - display_size)).max(Vec2::ZERO);
                 
-                egui::Frame::default()
-                    .margin(Margin {
-                        left: offset.x,
-                        top: offset.y,
-                        ..Default::default()
-                    })
-                    .show(ui, |ui| {
-                        egui::Frame::none()
-                        .shadow(ctx.style().visuals.popup_shadow)
+                let mut scroll_area = egui::ScrollArea::both().id_source("viewport");
+                if self.restore_offset {
+                    scroll_area = scroll_area.scroll_offset(self.viewport.offset);
+                    self.restore_offset = false;
+                }
+                
+                let output = scroll_area.show(ui, |ui| {
+                    egui::Frame::default()
+                        .margin(Margin {
+                            left: offset.x,
+                            top: offset.y,
+                            ..Default::default()
+                        })
                         .show(ui, |ui| {
-                            if ui.ctx().input().key_down(egui::Key::Space) {
-                                self.pipeline.show_original_image(ui);
-                            } else {
-                                self.pipeline.show_current_image(ui);
-                            }
+                            egui::Frame::none()
+                            .shadow(ctx.style().visuals.popup_shadow)
+                            .show(ui, |ui| {
+                                let response = if self.compare || self.hold_original {
+                                    self.pipeline.show_original_image(ui, display_size)
+                                } else {
+                                    self.pipeline.show_current_image(ui, display_size)
+                                };
+                                self.inspector.viewport_ui(ui, &self.pipeline, response);
+                            });
                         });
-                    });
+                });
+                self.viewport.offset = output.state.offset;
         
         });
         
+        // slider drags are recorded as single undo step when released
+        if self.uncommitted && !ctx.input().pointer.any_down() {
+            self.commit_history();
+        }
+        
+        if let Some(warning) = self.load_warning.take() {
+            toasts.warning(warning);
+        }
+        if let Some(message) = self.pipeline.take_soft_proof_error() {
+            toasts.error(format!("Soft proof failed: {message}"));
+        }
+        
         result
     }
+    
+    fn transparency_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.flatten, "Flatten onto background")
+                .on_hover_text("Replace transparency with background color on save, JPEG files are always flattened");
+            ui.color_edit_button_rgb(&mut self.background);
+        });
+    }
+    
+    fn output_profile_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Output profile:");
+            egui::ComboBox::from_id_source("output_profile")
+                .selected_text(self.output_profile.name())
+                .show_ui(ui, |ui| {
+                    for profile in ColorProfile::ALL {
+                        ui.selectable_value(&mut self.output_profile, profile, profile.name());
+                    }
+                });
+            ui.checkbox(&mut self.soft_proof, "Soft proof")
+                .on_hover_text("Preview the image as it will look like when saved in the output profile");
+        });
+        
+        self.pipeline.set_soft_proof(match self.soft_proof {
+            true => Some(self.output_profile),
+            false => None,
+        });
+    }
+    
+    fn clipping_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.show_clipping, "Show clipping")
+                .on_hover_text("Mark blown out highlights red and crushed shadows blue");
+            ui.add_enabled_ui(self.show_clipping, |ui| {
+                ui.checkbox(&mut self.clipping.per_channel, "Per channel")
+                    .on_hover_text("Mark pixels where any channel clips, otherwise all channels have to clip");
+            });
+        });
+        if self.show_clipping {
+            // threshold is edited in 8 bit levels which are easier to reason about
+            let mut levels = (self.clipping.threshold * 255.0).round();
+            ui.add(egui::Slider::new(&mut levels, 0.0..=50.0).step_by(1.0).text("Threshold"))
+                .on_hover_text("Distance from black or white in 8 bit levels which is still considered clipped");
+            self.clipping.threshold = levels / 255.0;
+        }
+        
+        self.pipeline.set_clipping(match self.show_clipping {
+            true => Some(self.clipping),
+            false => None,
+        });
+    }
+    
+    fn lut_export_ui(&mut self, ui: &mut egui::Ui, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            let export = ui.button("🎨 Export LUT")
+                .on_hover_text("Save color modifiers as 3D LUT in .cube format for use in other tools")
+                .clicked();
+            egui::ComboBox::from_id_source("lut_size")
+                .selected_text(format!("{0}×{0}×{0}", self.lut_size))
+                .show_ui(ui, |ui| {
+                    for size in LUT_EXPORT_SIZES {
+                        ui.selectable_value(&mut self.lut_size, *size, format!("{0}×{0}×{0}", size));
+                    }
+                });
+            
+            if export {
+                match self.export_lut() {
+                    Ok(Some((path, skipped))) => {
+                        toasts.info(format!("LUT saved to {}", path.display()));
+                        if !skipped.is_empty() {
+                            toasts.warning(format!(
+                                "Skipped modifiers which cannot be expressed by a LUT: {}",
+                                skipped.join(", ")
+                            ));
+                        }
+                    },
+                    Ok(None) => {},
+                    Err(message) => toasts.error(format!("LUT export failed: {message}")),
+                }
+            }
+        });
+    }
+    
+    fn metadata_ui(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(texts::sized("Metadata", 17.0))
+            .default_open(false)
+            .show(ui, |ui| {
+                let summary = self.media_file.metadata().summary();
+                if summary.is_empty() {
+                    ui.label("Image has no metadata");
+                } else {
+                    egui::Grid::new("metadata_grid")
+                        .num_columns(2)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (label, value) in summary {
+                                ui.label(label);
+                                ui.label(value);
+                                ui.end_row();
+                            }
+                        });
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("On save:");
+                    ui.radio_value(&mut self.metadata_export, MetadataExport::Keep, "Keep all");
+                    ui.radio_value(&mut self.metadata_export, MetadataExport::StripGps, "Strip GPS");
+                    ui.radio_value(&mut self.metadata_export, MetadataExport::StripAll, "Strip all");
+                });
+            });
+    }
 }
@@ -0,0 +1,162 @@
+
+use std::{
+    sync::{Arc, mpsc::{self, Receiver, Sender}},
+    thread,
+};
+
+use epi::backend::RepaintSignal;
+
+use crate::data::MultimediaFile;
+
+use super::{ImageEditor, Recipe, DocumentSession};
+
+enum LoaderMessage {
+    Progress(f32, &'static str),
+    Finished(Box<Result<ImageEditor, String>>),
+}
+
+/// Loads image file and prepares its editor on a background thread so the ui stays responsive.
+///   Loading is cancelled by dropping the loader, the thread then stops at the next stage.
+pub struct ImageLoader {
+    file_name: String,
+    receiver: Receiver<LoaderMessage>,
+    progress: f32,
+    stage: &'static str,
+    
+    /// when set then loaded image replaces editor with this id instead of opening a new tab
+    replaced_editor: Option<u64>,
+    /// recipe of the replaced editor when the user agreed to replace it,
+    ///   edits made while loading are detected by comparing with it
+    replaced_recipe: Option<Recipe>,
+    /// settings to be applied on loaded image
+    recipe: Option<Recipe>,
+    /// state of editor from previous run of the application
+    session: Option<DocumentSession>,
+}
+
+// constructors
+impl ImageLoader {
+    
+    pub fn start(file_name: &str, repaint_signal: Arc<dyn RepaintSignal>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let path = file_name.to_string();
+        thread::spawn(move || load(path, sender, repaint_signal));
+        
+        Self {
+            file_name: file_name.to_string(),
+            receiver,
+            progress: 0.0,
+            stage: "Opening file",
+            replaced_editor: None,
+            replaced_recipe: None,
+            recipe: None,
+            session: None,
+        }
+    }
+    
+    /// Loads image of a document from previous session and restores its editor state
+    pub fn restoring(session: DocumentSession, repaint_signal: Arc<dyn RepaintSignal>) -> Self {
+        let mut loader = Self::start(&session.path, repaint_signal);
+        loader.session = Some(session);
+        loader
+    }
+    
+    /// Loaded image will replace editor with given id with settings of given recipe
+    pub fn replacing(mut self, editor_id: u64, recipe: Option<Recipe>) -> Self {
+        self.replaced_editor = Some(editor_id);
+        self.recipe = recipe;
+        self
+    }
+}
+
+// properties
+impl ImageLoader {
+    
+    pub fn file_name(&self) -> &str {
+        self.file_name.as_str()
+    }
+    
+    pub fn replaced_editor(&self) -> Option<u64> {
+        self.replaced_editor
+    }
+    
+    pub fn replaced_recipe(&self) -> Option<&Recipe> {
+        self.replaced_recipe.as_ref()
+    }
+    
+    pub fn recipe(&self) -> Option<&Recipe> {
+        self.recipe.as_ref()
+    }
+    
+    pub fn session(&self) -> Option<&DocumentSession> {
+        self.session.as_ref()
+    }
+    
+    /// Estimated portion of work done in range 0.0 - 1.0
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+    
+    /// Description of what is currently being done
+    pub fn stage(&self) -> &'static str {
+        self.stage
+    }
+}
+
+// mutating methods
+impl ImageLoader {
+    
+    /// Remembers state of the replaced editor at the moment its replacement was confirmed
+    pub fn set_replaced_recipe(&mut self, recipe: Recipe) {
+        self.replaced_recipe = Some(recipe);
+    }
+    
+    /// Processes messages from the loading thread, returns result when loading is finished
+    pub fn poll(&mut self) -> Option<Result<ImageEditor, String>> {
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                LoaderMessage::Progress(progress, stage) => {
+                    self.progress = progress;
+                    self.stage = stage;
+                },
+                LoaderMessage::Finished(result) => return Some(*result),
+            }
+        }
+        None
+    }
+}
+
+/// Body of the loading thread
+fn load(path: String, sender: Sender<LoaderMessage>, repaint_signal: Arc<dyn RepaintSignal>) {
+    // sending fails only when the loader was dropped, which means loading was cancelled
+    let send = |message| {
+        let sent = sender.send(message).is_ok();
+        repaint_signal.request_repaint();
+        sent
+    };
+    
+    let mut media_file = match MultimediaFile::from_file(&path) {
+        Ok(media_file) => media_file,
+        Err(error) => {
+            send(LoaderMessage::Finished(Box::new(Err(error.to_string()))));
+            return;
+        },
+    };
+    
+    if !send(LoaderMessage::Progress(0.1, "Decoding image")) {
+        return;
+    }
+    let (original_image, warning) = match ImageEditor::decode_file(&mut media_file) {
+        Ok(decoded) => decoded,
+        Err(message) => {
+            send(LoaderMessage::Finished(Box::new(Err(message))));
+            return;
+        },
+    };
+    
+    if !send(LoaderMessage::Progress(0.7, "Generating previews")) {
+        return;
+    }
+    let editor = ImageEditor::new(media_file, original_image).with_load_warning(warning);
+    send(LoaderMessage::Finished(Box::new(Ok(editor))));
+}
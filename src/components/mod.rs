@@ -3,11 +3,21 @@ mod app;
 mod image_editor;
 mod modifier_pipeline;
 mod image;
+mod image_loader;
+mod filmstrip;
+mod session;
+mod pixel_inspector;
+mod scopes;
 
 pub use self::app::*;
 pub use self::image_editor::*;
 pub use self::image::*;
 pub use self::modifier_pipeline::*;
+pub use self::image_loader::*;
+pub use self::filmstrip::*;
+pub use self::session::*;
+pub use self::pixel_inspector::*;
+pub use self::scopes::*;
 
 // visible subfolders
 pub mod modifiers;
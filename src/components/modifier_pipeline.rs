@@ -1,12 +1,75 @@
 
 use image::imageops::FilterType;
+use serde::{Serialize, Deserialize};
 
-use super::Image;
-use super::modifiers::{ModifierResponse, Modifier};
+use crate::data::{ColorProfile, Lut, LutDimension};
+
+use super::{Image, ColorSpace};
+use super::modifiers::{ModifierResponse, Modifier, ModifierSettings};
+
+/// Settings of all modifiers in a pipeline which can be applied on another image
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Recipe {
+    /// settings identified by title of the modifier
+    pub modifiers: Vec<(String, ModifierSettings)>,
+}
+
+/// Marks areas of the image which are blown out to white or crushed to black
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ClippingOverlay {
+    /// distance from the end of range which is still considered clipped, in range 0.0 - 1.0
+    pub threshold: f32,
+    
+    /// when set then a single clipped channel marks the pixel, otherwise all channels have to clip
+    pub per_channel: bool,
+}
+
+impl Default for ClippingOverlay {
+    fn default() -> Self {
+        Self {
+            threshold: 0.0,
+            per_channel: true,
+        }
+    }
+}
+
+impl ClippingOverlay {
+    
+    pub const HIGHLIGHT_COLOR: [f32; 3] = [1.0, 0.0, 0.0];
+    pub const SHADOW_COLOR: [f32; 3] = [0.0, 0.0, 1.0];
+    
+    /// Returns copy of the image in perceptual space with clipped highlights tinted red and crushed shadows blue
+    pub fn apply(&self, image: &Image) -> Image {
+        let mut image = image.clone().into_color_space(ColorSpace::Perceptual);
+        
+        // values are compared as 8 bit levels, transfer functions do not map 1.0 exactly back to 1.0
+        let high = (255.0 * (1.0 - self.threshold)).round();
+        let low = (255.0 * self.threshold).round();
+        let per_channel = self.per_channel;
+        
+        image.map_pixels(|p| {
+            let rgb = [p[0], p[1], p[2]].map(|c| (c * 255.0).round());
+            let (highlight, shadow) = match per_channel {
+                true => (rgb.iter().any(|c| *c >= high), rgb.iter().any(|c| *c <= low)),
+                false => (rgb.iter().all(|c| *c >= high), rgb.iter().all(|c| *c <= low)),
+            };
+            
+            // fully transparent pixels are not visible so they cannot clip
+            let color = match (highlight, shadow) {
+                _ if p[3] == 0.0 => return,
+                (true, _) => Self::HIGHLIGHT_COLOR,
+                (false, true) => Self::SHADOW_COLOR,
+                _ => return,
+            };
+            *p = image::Rgba([color[0], color[1], color[2], 1.0]);
+        });
+        image
+    }
+}
 
 pub struct ModifierPipeline {
     
-    /// Image original in full size
+    /// Image original in full size converted into linear color space
     original_image: Image,
     
     /// Original copy in working resolution
@@ -24,6 +87,21 @@ pub struct ModifierPipeline {
     
     /// when set then modifier on this index will be rendered as active
     active_index: Option<usize>,
+    
+    /// when set then current image is rendered as it will look like when saved in this profile
+    soft_proof: Option<ColorProfile>,
+    
+    /// reason why the last soft proof could not be rendered, reported to the user once
+    soft_proof_error: Option<String>,
+    
+    /// when set then clipped areas are marked in the rendered current image
+    clipping: Option<ClippingOverlay>,
+    
+    /// current image with clipping overlay applied
+    clipping_image: Option<Image>,
+    
+    /// incremented with every evaluation so views derived from current image know when to update
+    revision: u64,
 }
 
 // construction
@@ -31,12 +109,17 @@ impl ModifierPipeline {
     
     pub fn new(original_image: Image, preview_size: egui::Vec2) -> Self {
         Self {
-            original_image,
+            original_image: original_image.into_color_space(ColorSpace::Linear),
             base_image: None,
             current_image: None,
             preview_size,
             modifiers: vec![],
             active_index: None,
+            soft_proof: None,
+            soft_proof_error: None,
+            clipping: None,
+            clipping_image: None,
+            revision: 0,
         }
     }
 
@@ -72,6 +155,20 @@ impl ModifierPipeline {
         &self.current_image.as_ref().unwrap_or(self.base_image())
     }
     
+    /// Number of evaluations of the pipeline, changes whenever current image changes
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+    
+    pub fn recipe(&self) -> Recipe {
+        Recipe {
+            modifiers: self.modifiers
+                .iter()
+                .map(|m| (m.title().to_string(), m.settings()))
+                .collect(),
+        }
+    }
+    
 }
 
 
@@ -82,6 +179,35 @@ impl ModifierPipeline {
         self.modifiers.push(modifier);
     }
     
+    /// Applies settings from recipe on modifiers with the same title
+    pub fn apply_recipe(&mut self, recipe: &Recipe) {
+        for (title, settings) in recipe.modifiers.iter() {
+            if let Some(modifier) = self.modifiers.iter_mut().find(|m| m.title() == title) {
+                modifier.set_settings(settings);
+            }
+        }
+        self.reevaluate();
+    }
+    
+    pub fn set_soft_proof(&mut self, soft_proof: Option<ColorProfile>) {
+        if self.soft_proof != soft_proof {
+            self.soft_proof = soft_proof;
+            self.reevaluate();
+        }
+    }
+    
+    /// Returns error of the last failed soft proof, the unproofed image is shown instead
+    pub fn take_soft_proof_error(&mut self) -> Option<String> {
+        self.soft_proof_error.take()
+    }
+    
+    pub fn set_clipping(&mut self, clipping: Option<ClippingOverlay>) {
+        if self.clipping != clipping {
+            self.clipping = clipping;
+            self.update_clipping_image();
+        }
+    }
+    
     pub fn resize(&mut self, size: egui::Vec2) {
         if self.preview_size != size {
             self.preview_size = size;
@@ -115,10 +241,62 @@ impl ModifierPipeline {
         
         // if there are any modifiers
         if self.modifiers.len() > 0 {
-            self.current_image = Some(self.modifiers
-                .iter().fold(image, |acc, m| m.apply(acc))
-            );
+            let image = self.modifiers
+                .iter().fold(image, |acc, m| m.process(acc))
+                .into_color_space(ColorSpace::Perceptual);
+            
+            self.current_image = Some(match self.soft_proof.map(|profile| image.soft_proofed(profile)) {
+                Some(Ok(proof)) => proof,
+                Some(Err(message)) => {
+                    self.soft_proof_error = Some(message);
+                    image
+                },
+                None => image,
+            });
         }
+        
+        self.revision += 1;
+        self.update_clipping_image();
+    }
+    
+    fn update_clipping_image(&mut self) {
+        self.clipping_image = self.clipping.map(|c| c.apply(self.current_image()));
+    }
+    
+    /// Bakes all pointwise modifiers into 3D LUT with given edge size.
+    ///   Returns titles of enabled modifiers which cannot be expressed by a LUT and were skipped.
+    pub fn export_lut(&self, size: usize, title: Option<String>) -> (Lut, Vec<String>) {
+        let lattice = Lut::identity_lattice(size);
+        
+        // lattice is laid out as an image with one row per blue and green index
+        let pixels: Vec<f32> = lattice.iter().flat_map(|[r, g, b]| [*r, *g, *b, 1.0]).collect();
+        let buffer = image::Rgba32FImage::from_raw(size as u32, (size * size) as u32, pixels)
+            .expect("lattice buffer has size of the image");
+        let lattice_image = Image::from_image(image::DynamicImage::ImageRgba32F(buffer));
+        
+        let (pointwise, spatial): (Vec<_>, Vec<_>) = self.modifiers
+            .iter()
+            .partition(|m| m.is_pointwise());
+        
+        let result = pointwise
+            .iter()
+            .fold(lattice_image, |acc, m| m.process(acc))
+            .perceptual_pixels();
+        
+        let lut = Lut {
+            title,
+            dimension: LutDimension::ThreeD,
+            size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            table: result.pixels().map(|p| [p[0], p[1], p[2]]).collect(),
+        };
+        let skipped = spatial
+            .iter()
+            .filter(|m| m.settings().enabled)
+            .map(|m| m.title().to_string())
+            .collect();
+        (lut, skipped)
     }
     
     /// returns a copy of original image with all the modifiers applied in full resolution
@@ -127,8 +305,9 @@ impl ModifierPipeline {
             .iter()
             .fold(
                 self.original_image.clone(),
-                |acc, m| m.apply(acc)
+                |acc, m| m.process(acc)
             )
+            .into_color_space(ColorSpace::Perceptual)
     }
 }
 
@@ -136,22 +315,28 @@ impl ModifierPipeline {
 // rendering
 impl ModifierPipeline {
     
-    pub fn show_current_image(&mut self, ui: &mut egui::Ui) {
+    /// Shows result image scaled to given size which may differ from the preview size when zoomed in
+    pub fn show_current_image(&mut self, ui: &mut egui::Ui, size: egui::Vec2) -> egui::Response {
+        if let Some(image) = &mut self.clipping_image {
+            return image.show_over_checkerboard(ui, size);
+        }
+        
         // show image if defined in this order: current ?? base ?? original
         self.current_image
             .as_mut()
             .unwrap_or(self.base_image.as_mut().unwrap_or(&mut self.original_image))
-            .show_sized(ui, self.preview_size);
+            .show_over_checkerboard(ui, size)
     }
     
-    pub fn show_original_image(&mut self, ui: &mut egui::Ui) {
+    pub fn show_original_image(&mut self, ui: &mut egui::Ui, size: egui::Vec2) -> egui::Response {
         self.base_image
             .as_mut()
             .unwrap_or(&mut self.original_image)
-            .show_sized(ui, self.preview_size);
+            .show_over_checkerboard(ui, size)
     }
     
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    /// Renders ui of all modifiers and returns action of modifier the user interacted with
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
         let mut modifier_action = ModifierResponse::Nothing;
         let mut on_index = 0;
         
@@ -179,5 +364,7 @@ impl ModifierPipeline {
             ModifierResponse::Changed => self.reevaluate(),
             _ => ()
         }
+        
+        modifier_action
     }
 }
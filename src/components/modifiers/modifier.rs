@@ -1,5 +1,9 @@
 
-use crate::components::Image;
+use std::path::PathBuf;
+
+use serde::{Serialize, Deserialize};
+
+use crate::components::{Image, ColorSpace};
 
 #[derive(PartialEq)]
 pub enum ModifierResponse {
@@ -12,12 +16,51 @@ pub trait ModifierUi {
     fn ui(&mut self, ui: &mut egui::Ui) -> ModifierResponse;
 }
 
-pub trait Modifier : ModifierUi {
+/// Adjustable values of a modifier, used to transfer settings between images
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ModifierSettings {
+    pub enabled: bool,
+    pub values: Vec<f32>,
+    
+    /// file the modifier reads its data from, like a LUT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+pub trait ModifierState {
+    fn settings(&self) -> ModifierSettings;
+    
+    /// Applies settings taken from the same kind of modifier, missing values are left unchanged
+    fn set_settings(&mut self, settings: &ModifierSettings);
+}
+
+/// Modifiers are created on the background thread loading the image, so they have to be `Send`
+pub trait Modifier : ModifierUi + ModifierState + Send {
     
     fn title(&self) -> &str;
     
+    /// Color space in which `apply` expects its input image
+    fn color_space(&self) -> ColorSpace;
+    
+    /// Whether current settings leave every image unchanged
+    fn is_neutral(&self) -> bool;
+    
     fn apply(&self, image: Image) -> Image;
     
+    /// Whether every output pixel depends only on the same input pixel, so the modifier can be baked into a LUT
+    fn is_pointwise(&self) -> bool {
+        true
+    }
+    
+    /// Converts image into color space of this modifier and applies the modifier on it,
+    ///   neutral modifier returns the image as it is without converting it
+    fn process(&self, image: Image) -> Image {
+        if self.is_neutral() {
+            return image;
+        }
+        self.apply(image.into_color_space(self.color_space()))
+    }
+    
     fn show_preview(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
         ui.group(|ui| self.ui(ui)).inner
     }
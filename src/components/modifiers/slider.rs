@@ -1,6 +1,6 @@
 
 use crate::{components::Image, constants::{THUMBNAIL_SIZE, RIGHT_PANEL_WIDTH}, widgets::texts};
-use super::{Modifier, ModifierResponse, ModifierUi};
+use super::{Modifier, ModifierResponse, ModifierUi, ModifierSettings, ModifierState};
 
 pub trait Slider : Modifier + Default {
     
@@ -34,11 +34,11 @@ pub trait Slider : Modifier + Default {
         
         // create thumbnail of low applied filter
         tmp_instance.set_percent(tmp_instance.min_percent() * 0.8);
-        let low_image = tmp_instance.apply(thumbnail.clone());
+        let low_image = tmp_instance.process(thumbnail.clone());
         
         // create thumbnail of high applied filter
         tmp_instance.set_percent(tmp_instance.max_percent() * 0.8);
-        let high_image = tmp_instance.apply(thumbnail);
+        let high_image = tmp_instance.process(thumbnail);
         
         // create true result and attach the generated previews
         let mut this = Self::default();
@@ -123,6 +123,30 @@ impl<T: SliderCommonUiImpl> ModifierUi for T {
     }
 }
 
+// Trait with common implementation for slider settings
+pub(crate) trait SliderCommonStateImpl : Slider {
+    fn settings(&self) -> ModifierSettings {
+        ModifierSettings { enabled: self.enabled(), values: vec![self.percent()], path: None }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+    }
+}
+
+impl<T: SliderCommonStateImpl> ModifierState for T {
+    fn settings(&self) -> ModifierSettings {
+        SliderCommonStateImpl::settings(self)
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        SliderCommonStateImpl::set_settings(self, settings)
+    }
+}
+
 // Trait with common implementation for slider data
 pub(crate) struct SliderData {
     pub enabled: bool,
@@ -1,7 +1,6 @@
 
-use image::{GenericImageView, GenericImage};
-
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 pub struct BMulModifier {
     data : SliderData
@@ -28,32 +27,33 @@ impl SliderCommonDataImp for BMulModifier {
 
 impl SliderCommonUiImpl for BMulModifier {}
 
+impl SliderCommonStateImpl for BMulModifier {}
+
 impl Modifier for BMulModifier {
     fn title(&self) -> &str {
         "Brightness multiply"
     }
     
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Linear
+    }
+    
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 1.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 1.0 {
+        if self.is_neutral() {
             return image;
         }
         
         let coefficient = self.percent();
         
-        let img = &mut image.raw_image;
-        let (width, height) = img.dimensions();
-        
-        for y in 0..height {
-            for x in 0..width {
-                let mut pixel = img.get_pixel(x, y);
-                for i in 0..3 {
-                    let mut v = pixel[i] as f32;
-                    v *= coefficient;
-                    pixel[i] = v.clamp(0.0, 255.0) as u8;
-                }
-                img.put_pixel(x, y, pixel);
+        image.map_pixels(|pixel| {
+            for i in 0..3 {
+                pixel[i] = (pixel[i] * coefficient).clamp(0.0, 1.0);
             }
-        }
+        });
         
         image
     }
@@ -1,8 +1,31 @@
 
 use cgmath::{num_traits::Pow, Matrix3, Vector3, Matrix};
-use image::{GenericImageView, GenericImage};
 
-use crate::{components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData, ModifierUi, ModifierResponse}, constants::THUMBNAIL_SIZE, widgets::texts};
+use crate::{components::ColorSpace, components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, ModifierSettings, Slider, SliderData, ModifierUi, ModifierResponse}, constants::THUMBNAIL_SIZE, widgets::texts};
+
+// RGB <=> YUV transforms taken from SDTV with BT.470, matrices are written by rows
+// https://en.wikipedia.org/wiki/YUV#:~:text=Equal%20values%20of%20red%2C%20green,video%20cameras%20use%20Y%E2%80%B2CbCr.
+const RGB_TO_YUV: Matrix3<f32> = Matrix3::new(
+    0.299, 0.587, 0.114,
+    -0.14713, -0.28886, 0.436,
+    0.615, -0.51499, -0.10001
+);
+
+const YUV_TO_RGB: Matrix3<f32> = Matrix3::new(
+    1.0, 0.0, 1.13983,
+    1.0, -0.39465, -0.58060,
+    1.0, 2.03211, 0.0
+);
+
+/// Matrix converting RGB column vector into YUV
+pub fn rgb_to_yuv_matrix() -> Matrix3<f32> {
+    RGB_TO_YUV.transpose()
+}
+
+/// Matrix converting YUV column vector into RGB
+pub fn yuv_to_rgb_matrix() -> Matrix3<f32> {
+    YUV_TO_RGB.transpose()
+}
 
 pub struct BMulLumaModifier {
     gamma: f32,
@@ -46,54 +69,54 @@ impl SliderCommonUiImpl for BMulLumaModifier {
     }
 }
 
+impl SliderCommonStateImpl for BMulLumaModifier {
+    fn settings(&self) -> ModifierSettings {
+        ModifierSettings { enabled: self.enabled(), values: vec![self.percent(), self.gamma], path: None }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let [percent, gamma, ..] = settings.values[..] {
+            self.set_percent(percent);
+            self.gamma = gamma.clamp(0.1, 1.5);
+        }
+    }
+}
+
 impl Modifier for BMulLumaModifier {
     fn title(&self) -> &str {
         "Brightness multiply luma"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
         
         let coefficient = self.percent();
-        let img = &mut image.raw_image;
-        let (width, height) = img.dimensions();
-        
-        // RGB <=> YUV transforms taken from SDTV with BT.470
-        // https://en.wikipedia.org/wiki/YUV#:~:text=Equal%20values%20of%20red%2C%20green,video%20cameras%20use%20Y%E2%80%B2CbCr.
-        let rgb_to_yuv = Matrix3::<f32>::new(
-            0.299, 0.587, 0.114,
-            -0.14713, -0.28886, 0.436,
-            0.615, -0.51499, -0.10001
-        ).transpose();
+        let gamma = self.gamma;
         
-        let yuv_to_rgb = Matrix3::<f32>::new(
-            1.0, 0.0, 1.13983,
-            1.0, -0.39465, -0.58060,
-            1.0, 2.03211, 0.0
-        ).transpose();
+        let rgb_to_yuv = rgb_to_yuv_matrix();
+        let yuv_to_rgb = yuv_to_rgb_matrix();
         
-        for y in 0..height {
-            for x in 0..width {
-                let mut pixel = img.get_pixel(x, y);
-                
-                let rgb = Vector3::<f32>::new(
-                    pixel[0] as f32,
-                    pixel[1] as f32,
-                    pixel[2] as f32
-                ) / 255.0;
-                
-                let mut yuv = rgb_to_yuv * rgb;
-                yuv.x *= 1.0 + coefficient * (1.0 - yuv.x.pow(self.gamma));
-                let rgb = yuv_to_rgb * yuv;
-                pixel[0] = (rgb.x * 255.0).clamp(0.0, 255.0) as u8;
-                pixel[1] = (rgb.y * 255.0).clamp(0.0, 255.0) as u8;
-                pixel[2] = (rgb.z * 255.0).clamp(0.0, 255.0) as u8;
-                
-                img.put_pixel(x, y, pixel);
-            }
-        }
+        image.map_pixels(|pixel| {
+            let rgb = Vector3::<f32>::new(pixel[0], pixel[1], pixel[2]);
+            
+            let mut yuv = rgb_to_yuv * rgb;
+            yuv.x *= 1.0 + coefficient * (1.0 - yuv.x.pow(gamma));
+            let rgb = yuv_to_rgb * yuv;
+            pixel[0] = rgb.x.clamp(0.0, 1.0);
+            pixel[1] = rgb.y.clamp(0.0, 1.0);
+            pixel[2] = rgb.z.clamp(0.0, 1.0);
+        });
         
         image
     }
@@ -1,7 +1,8 @@
 
 use crate::utils::math::lramp;
 
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 const MAX_BLUR: f32 = 3.0;
 
@@ -28,16 +29,33 @@ impl SliderCommonDataImp for BlurModifier {
 
 impl SliderCommonUiImpl for BlurModifier {}
 
+impl SliderCommonStateImpl for BlurModifier {}
+
 impl Modifier for BlurModifier {
     fn title(&self) -> &str {
         "Blur"
     }
+    
+    fn is_pointwise(&self) -> bool {
+        false
+    }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Linear
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
-        image.raw_image =  image.raw_image.blur(lramp(0.0, MAX_BLUR, self.percent() * 0.01));
+        
+        // sigma is given in pixels of the original image
+        let sigma = lramp(0.0, MAX_BLUR, self.percent() * 0.01) * image.scale();
+        image.map_buffer_premultiplied(|source| image::imageops::blur(source, sigma));
         image
     }
 }
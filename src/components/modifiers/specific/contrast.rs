@@ -1,7 +1,6 @@
 
-use image::imageops::colorops;
-
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 pub struct ContrastModifier {
     data : SliderData
@@ -24,20 +23,34 @@ impl SliderCommonDataImp for ContrastModifier {
 
 impl SliderCommonUiImpl for ContrastModifier {}
 
+impl SliderCommonStateImpl for ContrastModifier {}
+
 impl Modifier for ContrastModifier {
     fn title(&self) -> &str {
         "Contrast"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
-          
-        colorops::contrast_in_place(
-            &mut image.raw_image,
-            self.percent() * 0.6,
-        );
+        
+        // same curve as `image::imageops::colorops::contrast_in_place` but alpha is left untouched
+        let coefficient = ((100.0 + self.percent() * 0.6) / 100.0).powi(2);
+        
+        image.map_pixels(|pixel| {
+            for i in 0..3 {
+                pixel[i] = ((pixel[i] - 0.5) * coefficient + 0.5).clamp(0.0, 1.0);
+            }
+        });
         
         image
     }
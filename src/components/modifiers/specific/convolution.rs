@@ -0,0 +1,402 @@
+use image::Rgba32FImage;
+
+use crate::components::ColorSpace;
+use crate::constants::THUMBNAIL_SIZE;
+use crate::widgets::texts;
+use crate::utils::math::lramp;
+
+use crate::components::modifiers::{SliderCommonDataImp, Modifier, Slider, SliderData, ModifierUi, ModifierResponse, ModifierState, ModifierSettings};
+
+const KERNEL_SIZES: [usize; 3] = [3, 5, 7];
+
+/// How pixels outside of the image are sampled
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeMode {
+    /// repeat the nearest edge pixel
+    Clamp,
+    /// continue from the opposite side of the image
+    Wrap,
+    /// reflect the image over its edge
+    Mirror,
+}
+
+impl EdgeMode {
+    pub const ALL: [EdgeMode; 3] = [EdgeMode::Clamp, EdgeMode::Wrap, EdgeMode::Mirror];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            EdgeMode::Clamp => "Clamp",
+            EdgeMode::Wrap => "Wrap",
+            EdgeMode::Mirror => "Mirror",
+        }
+    }
+    
+    /// Maps coordinate outside of `0..length` into the image
+    fn resolve(&self, i: i64, length: i64) -> usize {
+        let i = match self {
+            EdgeMode::Clamp => i,
+            EdgeMode::Wrap => i.rem_euclid(length),
+            EdgeMode::Mirror if i < 0 => -i,
+            EdgeMode::Mirror if i >= length => 2 * length - 2 - i,
+            EdgeMode::Mirror => i,
+        };
+        // images smaller than the kernel may still be out of range after reflection
+        i.clamp(0, length - 1) as usize
+    }
+}
+
+/// Convolves the image with user defined kernel, alpha channel is left untouched
+pub struct ConvolutionModifier {
+    data : SliderData,
+    size: usize,
+    /// kernel values by rows
+    kernel: Vec<f32>,
+    /// kernel is divided by sum of its values so overall brightness is kept
+    normalize: bool,
+    /// constant added to result, e.g. to center emboss around gray
+    bias: f32,
+    edges: EdgeMode,
+}
+
+impl Default for ConvolutionModifier {
+    fn default() -> Self {
+        Self {
+            data: SliderData {
+                percent: 100.0,
+                min: 0.0,
+                max: 100.0,
+                ..Default::default()
+            },
+            size: 3,
+            kernel: vec![
+                0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0,
+                0.0, 0.0, 0.0,
+            ],
+            normalize: true,
+            bias: 0.0,
+            edges: EdgeMode::Clamp,
+        }
+    }
+}
+
+impl SliderCommonDataImp for ConvolutionModifier {
+    fn slider_data(&self) -> &SliderData { &self.data }
+    fn slider_data_mut(&mut self) -> &mut SliderData { &mut self.data }
+}
+
+impl ModifierUi for ConvolutionModifier {
+    fn ui(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
+        
+        let mut res = ModifierResponse::Nothing;
+        let mut kernel_changed = false;
+        
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                if ui.checkbox(self.enabled_mut(), texts::sized("Convolution:", 20.0)).changed() {
+                    res = ModifierResponse::Changed;
+                }
+                ui.add_space(ui.available_width() - 55.0);
+                if ui.button(texts::sized("Reset", 17.0)).clicked() {
+                    let enabled = self.enabled();
+                    let thumbnails = (self.data.min_thumbnail.take(), self.data.max_thumbnail.take());
+                    *self = Self::default();
+                    *self.enabled_mut() = enabled;
+                    (self.data.min_thumbnail, self.data.max_thumbnail) = thumbnails;
+                    res = ModifierResponse::Changed;
+                    kernel_changed = true;
+                }
+            });
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                macro_rules! draw_image_option { ($e:expr) => {
+                    if let Some(image) = $e {
+                        ui.vertical(|ui| {
+                            ui.set_height(THUMBNAIL_SIZE);
+                            image.show_sized(ui, [THUMBNAIL_SIZE, THUMBNAIL_SIZE].into());
+                        });
+                    };
+                }}
+                
+                draw_image_option!(self.min_thumbnail());
+                ui.vertical(|ui| {
+                    
+                    let min = self.min_percent();
+                    let max = self.max_percent();
+                    let mut percent = self.percent();
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().slider_width = ui.available_width() - THUMBNAIL_SIZE * 2.0 - 30.0;
+                        ui.add(egui::Slider::new(&mut percent, min..=max).clamp_to_range(true));
+                        ui.label(self.units_name());
+                    });
+                    
+                    if percent != self.percent() {
+                        self.set_percent(percent);
+                        res = ModifierResponse::Changed;
+                    }
+                    
+                    if self.settings_ui(ui) {
+                        res = ModifierResponse::Changed;
+                        kernel_changed = true;
+                    }
+                });
+                draw_image_option!(self.max_thumbnail());
+            });
+            
+            // kernel editor
+            let size = self.size;
+            let mut kernel = self.kernel.clone();
+            egui::Grid::new("convolution_kernel")
+                .spacing([4.0, 4.0])
+                .show(ui, |ui| {
+                    for row in kernel.chunks_mut(size) {
+                        for value in row {
+                            ui.add(egui::DragValue::new(value).speed(0.01).clamp_range(-10.0..=10.0));
+                        }
+                        ui.end_row();
+                    }
+                });
+            if kernel != self.kernel {
+                self.kernel = kernel;
+                res = ModifierResponse::Changed;
+                kernel_changed = true;
+            }
+            
+            // select presets
+            ui.horizontal_wrapped(|ui| {
+                for (label, size, kernel, normalize, bias) in PREDEFINED_KERNELS {
+                    if ui.button(*label).clicked() {
+                        self.size = *size;
+                        self.kernel = kernel.to_vec();
+                        self.normalize = *normalize;
+                        self.bias = *bias;
+                        res = ModifierResponse::Changed;
+                        kernel_changed = true;
+                    }
+                }
+            });
+        });
+        
+        if kernel_changed {
+            self.update_max_thumbnail();
+        }
+        
+        res
+    }
+}
+
+impl ConvolutionModifier {
+    
+    /// Renders kernel size, normalisation, bias and edge handling, returns whether any of them changed
+    fn settings_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        
+        ui.horizontal(|ui| {
+            let mut size = self.size;
+            egui::ComboBox::from_id_source("convolution_size")
+                .width(60.0)
+                .selected_text(format!("{0}×{0}", size))
+                .show_ui(ui, |ui| {
+                    for s in KERNEL_SIZES {
+                        ui.selectable_value(&mut size, s, format!("{0}×{0}", s));
+                    }
+                });
+            if size != self.size {
+                self.resize_kernel(size);
+                changed = true;
+            }
+            
+            let before = self.edges;
+            egui::ComboBox::from_id_source("convolution_edges")
+                .width(70.0)
+                .selected_text(self.edges.name())
+                .show_ui(ui, |ui| {
+                    for edges in EdgeMode::ALL {
+                        ui.selectable_value(&mut self.edges, edges, edges.name());
+                    }
+                });
+            changed |= before != self.edges;
+        });
+        
+        ui.horizontal(|ui| {
+            changed |= ui.checkbox(&mut self.normalize, "Normalize")
+                .on_hover_text("Divide kernel by sum of its values to keep brightness")
+                .changed();
+            ui.label("Bias:");
+            changed |= ui.add(egui::DragValue::new(&mut self.bias).speed(0.01).clamp_range(-1.0..=1.0)).changed();
+        });
+        
+        changed
+    }
+    
+    /// Changes kernel size keeping values around the center
+    fn resize_kernel(&mut self, size: usize) {
+        let mut kernel = vec![0.0; size * size];
+        let old = self.size as i64;
+        let shift = (size as i64 - old) / 2;
+        for y in 0..old {
+            for x in 0..old {
+                let (nx, ny) = (x + shift, y + shift);
+                if (0..size as i64).contains(&nx) && (0..size as i64).contains(&ny) {
+                    kernel[(ny * size as i64 + nx) as usize] = self.kernel[(y * old + x) as usize];
+                }
+            }
+        }
+        self.size = size;
+        self.kernel = kernel;
+    }
+    
+    /// Kernel with normalisation applied
+    fn effective_kernel(&self) -> Vec<f32> {
+        let sum: f32 = self.kernel.iter().sum();
+        match self.normalize && sum.abs() > f32::EPSILON {
+            true => self.kernel.iter().map(|v| v / sum).collect(),
+            false => self.kernel.clone(),
+        }
+    }
+    
+    /// renders max settings thumbnail with current kernel
+    fn update_max_thumbnail(&mut self) {
+        if let Some(min_i) = self.data.min_thumbnail.clone() {
+            let p = self.percent();
+            self.set_percent(100.0);
+            *self.max_thumbnail() = Some(self.process(min_i));
+            self.set_percent(p);
+        }
+    }
+}
+
+impl ModifierState for ConvolutionModifier {
+    /// Values are percent, kernel size, normalize flag, bias, edge mode and kernel by rows
+    fn settings(&self) -> ModifierSettings {
+        let edges = EdgeMode::ALL.iter().position(|e| *e == self.edges).unwrap_or(0);
+        let header = [self.percent(), self.size as f32, self.normalize as u8 as f32, self.bias, edges as f32];
+        ModifierSettings {
+            enabled: self.enabled(),
+            values: [&header[..], &self.kernel[..]].concat(),
+            path: None,
+        }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        let values = &settings.values;
+        if let Some(percent) = values.first() {
+            self.set_percent(*percent);
+        }
+        if let [_, size, normalize, bias, edges, kernel @ ..] = &values[..] {
+            let size = *size as usize;
+            if KERNEL_SIZES.contains(&size) && kernel.len() == size * size {
+                self.size = size;
+                self.kernel = kernel.to_vec();
+                self.normalize = *normalize != 0.0;
+                self.bias = *bias;
+                self.edges = EdgeMode::ALL.get(*edges as usize).copied().unwrap_or(EdgeMode::Clamp);
+                self.update_max_thumbnail();
+            }
+        }
+    }
+}
+
+impl Modifier for ConvolutionModifier {
+    fn title(&self) -> &str {
+        "Convolution"
+    }
+    
+    fn is_pointwise(&self) -> bool {
+        false
+    }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
+    
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
+    fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
+        if self.is_neutral() {
+            return image;
+        }
+        
+        let level = self.percent() / 100.0;
+        let kernel = self.effective_kernel();
+        image.map_buffer_premultiplied(|source| {
+            let mut result = convolve(source, &kernel, self.size, self.edges, self.bias);
+            for (pixel, original) in result.pixels_mut().zip(source.pixels()) {
+                for i in 0..3 {
+                    pixel[i] = lramp(original[i], pixel[i], level).clamp(0.0, original[3]);
+                }
+            }
+            result
+        });
+        image
+    }
+}
+
+/// Convolves premultiplied color channels of the image, alpha is copied from the source
+fn convolve(image: &Rgba32FImage, kernel: &[f32], size: usize, edges: EdgeMode, bias: f32) -> Rgba32FImage {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    let radius = (size / 2) as i64;
+    let mut result = image.clone();
+    
+    for (x, y, pixel) in result.enumerate_pixels_mut() {
+        // bias is premultiplied like the colors
+        let mut sum = [bias * pixel[3]; 3];
+        for ky in 0..size as i64 {
+            let sy = edges.resolve(y as i64 + ky - radius, h) as u32;
+            for kx in 0..size as i64 {
+                let weight = kernel[(ky * size as i64 + kx) as usize];
+                if weight == 0.0 {
+                    continue;
+                }
+                let sx = edges.resolve(x as i64 + kx - radius, w) as u32;
+                let source = image.get_pixel(sx, sy);
+                for i in 0..3 {
+                    sum[i] += source[i] * weight;
+                }
+            }
+        }
+        for i in 0..3 {
+            pixel[i] = sum[i];
+        }
+    }
+    result
+}
+
+/// name, size, kernel by rows, normalize, bias
+static PREDEFINED_KERNELS: &[(&str, usize, &[f32], bool, f32)] = &[
+    (
+        "Sharpen", 3, &[
+            0.0, -1.0, 0.0,
+            -1.0, 5.0, -1.0,
+            0.0, -1.0, 0.0,
+        ], true, 0.0
+    ),
+    (
+        "Emboss", 3, &[
+            -2.0, -1.0, 0.0,
+            -1.0, 1.0, 1.0,
+            0.0, 1.0, 2.0,
+        ], true, 0.0
+    ),
+    (
+        "Edge Detect", 3, &[
+            -1.0, -1.0, -1.0,
+            -1.0, 8.0, -1.0,
+            -1.0, -1.0, -1.0,
+        ], false, 0.0
+    ),
+    (
+        "Box Blur", 3, &[
+            1.0, 1.0, 1.0,
+            1.0, 1.0, 1.0,
+            1.0, 1.0, 1.0,
+        ], true, 0.0
+    ),
+    (
+        "Box Blur 5×5", 5, &[1.0; 25], true, 0.0
+    ),
+];
@@ -1,16 +1,31 @@
 
-use cgmath::{Matrix3, Vector3, Matrix};
-use image::{GenericImageView, GenericImage};
+use cgmath::{Matrix3, Matrix4, Vector4, Matrix, SquareMatrix};
 
+use crate::components::ColorSpace;
 use crate::constants::THUMBNAIL_SIZE;
 use crate::widgets::texts;
-use crate::utils::math::lramp;
+use crate::utils::{math::lramp, load_presets_file, save_presets_file};
+use crate::data::USER_MATRIX_PRESETS;
 
-use crate::components::modifiers::{SliderCommonDataImp, Modifier, Slider, SliderData, ModifierUi, ModifierResponse};
+use crate::components::modifiers::{SliderCommonDataImp, Modifier, Slider, SliderData, ModifierUi, ModifierResponse, ModifierState, ModifierSettings};
 
 pub struct CustomModifier {
     data : SliderData,
-    grading_matrix: Matrix3<f32>,
+    
+    /// Affine transform of `[r, g, b, 1]`, displayed rows are indexed like `grading_matrix[row][column]`.
+    ///   Last column holds offsets, last row multiplies alpha and stays identity unless alpha row is used.
+    grading_matrix: Matrix4<f32>,
+    /// offset column is shown in the editor
+    affine: bool,
+    /// alpha row is shown in the editor
+    alpha_row: bool,
+    
+    /// name under which the current matrix is saved as user preset
+    preset_name: String,
+    /// index of user preset being renamed with the edited name
+    renaming: Option<(usize, String)>,
+    /// result of the last preset operation shown to the user
+    preset_message: Option<Result<String, String>>,
 }
 
 impl Default for CustomModifier {
@@ -21,7 +36,12 @@ impl Default for CustomModifier {
                 max: 100.0,
                 ..Default::default()
             },
-            grading_matrix: Matrix3::from_scale(1.0),
+            grading_matrix: Matrix4::identity(),
+            affine: false,
+            alpha_row: false,
+            preset_name: String::new(),
+            renaming: None,
+            preset_message: None,
         }
     }
 }
@@ -47,7 +67,10 @@ impl ModifierUi for CustomModifier {
                     let d = Self::default();
                     *self.percent_mut() = d.percent();
                     self.grading_matrix = d.grading_matrix;
+                    self.affine = d.affine;
+                    self.alpha_row = d.alpha_row;
                     res = ModifierResponse::Changed;
+                    matrix_changed = true;
                 }
             });
             ui.separator();
@@ -80,12 +103,35 @@ impl ModifierUi for CustomModifier {
                         res = ModifierResponse::Changed;
                     }
                     
+                    // hiding a part of the matrix resets it so hidden values never affect the image
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut self.affine, "Offsets").on_hover_text("Add constant to every channel").changed() {
+                            if !self.affine {
+                                for i in 0..3 {
+                                    self.grading_matrix[i][3] = 0.0;
+                                }
+                            }
+                            res = ModifierResponse::Changed;
+                            matrix_changed = true;
+                        }
+                        if ui.checkbox(&mut self.alpha_row, "Alpha row").on_hover_text("Multiply alpha by combination of channels").changed() {
+                            if !self.alpha_row {
+                                self.grading_matrix[3] = Vector4::unit_w();
+                            }
+                            res = ModifierResponse::Changed;
+                            matrix_changed = true;
+                        }
+                    });
+                    
                     let rgb = ["R", "G", "B"];
-                    for (i, label) in rgb.iter().enumerate() {
+                    let rows = if self.alpha_row { 4 } else { 3 };
+                    for i in 0..rows {
                         let mut row = self.grading_matrix[i];
                         ui.horizontal(|ui| {
-                            ui.label(*label);
-                            ui.label("=");
+                            match i {
+                                3 => { ui.label("A = A ×"); },
+                                _ => { ui.label(rgb[i]); ui.label("="); },
+                            }
                             for (i, label) in rgb.iter().enumerate() {
                                 ui.add(
                                     egui::DragValue::new(&mut row[i])
@@ -96,6 +142,13 @@ impl ModifierUi for CustomModifier {
                                     ui.label("+");
                                 }
                             }
+                            if self.affine || i == 3 {
+                                ui.label("+");
+                                ui.add(
+                                    egui::DragValue::new(&mut row[3])
+                                        .speed(0.001).clamp_range(-1.0..=1.0)
+                                );
+                            }
                         });
                         
                         if row != self.grading_matrix[i] {
@@ -112,30 +165,192 @@ impl ModifierUi for CustomModifier {
             });
             
             // select presets
+            if self.presets_ui(ui) {
+                res = ModifierResponse::Changed;
+                matrix_changed = true;
+            }
             
-            ui.horizontal(|ui| {
-                for (label, mat) in PREDEFINED_FILTERS.iter() {
-                    if ui.button(*label).clicked() {
-                        self.grading_matrix = *mat;
-                        res = ModifierResponse::Changed;
-                        matrix_changed = true;
-                    }
+        });
+        
+        if matrix_changed {
+            self.update_max_thumbnail();
+        }
+        
+        res
+    }
+}
+
+impl CustomModifier {
+    
+    /// Renders built-in and user presets with their management, returns whether a preset was applied
+    fn presets_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut applied = None;
+        let mut presets = USER_MATRIX_PRESETS.lock().unwrap();
+        let mut changed = false;
+        
+        ui.horizontal_wrapped(|ui| {
+            for (label, mat) in PREDEFINED_FILTERS.iter() {
+                if ui.button(*label).clicked() {
+                    applied = Some(Matrix4::from(*mat));
                 }
-            });
+            }
+            
+            // user presets are managed from their context menu
+            let mut removed = None;
+            for (i, preset) in presets.presets().iter().enumerate() {
+                if matches!(&self.renaming, Some((index, _)) if *index == i) {
+                    continue;
+                }
+                let response = ui.button(&preset.name).on_hover_text("Right click to rename or delete");
+                if response.clicked() {
+                    let mut matrix = Matrix4::identity();
+                    let values: &mut [f32; 16] = matrix.as_mut();
+                    values.copy_from_slice(&preset.matrix);
+                    applied = Some(matrix);
+                }
+                response.context_menu(|ui| {
+                    if ui.button("Rename").clicked() {
+                        self.renaming = Some((i, preset.name.clone()));
+                        ui.close_menu();
+                    }
+                    if ui.button("Delete").clicked() {
+                        removed = Some(i);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if let Some(i) = removed {
+                presets.remove(i);
+                changed = true;
+                
+                // preset being renamed moves with the rest of the list
+                self.renaming = match self.renaming.take() {
+                    Some((index, _)) if index == i => None,
+                    Some((index, name)) if index > i => Some((index - 1, name)),
+                    renaming => renaming,
+                };
+            }
             
+            if let Some((index, name)) = &mut self.renaming {
+                let response = ui.text_edit_singleline(name);
+                if response.lost_focus() {
+                    let name = name.trim().to_string();
+                    if ui.input().key_pressed(egui::Key::Enter) && !name.is_empty() {
+                        match presets.rename(*index, &name) {
+                            Ok(()) => changed = true,
+                            Err(message) => self.preset_message = Some(Err(message)),
+                        }
+                    }
+                    self.renaming = None;
+                } else {
+                    response.request_focus();
+                }
+            }
         });
         
-         // update max settings thumbnail
-         if matrix_changed {
-            if let Some(min_i) = self.data.min_thumbnail.clone() {
-                let p = self.percent();
-                self.set_percent(100.0);
-                *self.max_thumbnail() = Some(self.apply(min_i));
-                self.set_percent(p);
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.preset_name).hint_text("Preset name").desired_width(120.0));
+            let name = self.preset_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save preset")).clicked() {
+                let matrix: &[f32; 16] = self.grading_matrix.as_ref();
+                match presets.add(&name, *matrix) {
+                    Ok(()) => {
+                        self.preset_name.clear();
+                        changed = true;
+                    },
+                    Err(message) => self.preset_message = Some(Err(message)),
+                }
+            }
+            if ui.button("Import").on_hover_text("Add presets from a file").clicked() {
+                if let Some(path) = load_presets_file() {
+                    let imported = presets.import(&path);
+                    changed |= imported.is_ok();
+                    self.preset_message = Some(imported.map(|count| format!("Imported {count} presets")));
+                }
             }
+            if ui.button("Export").on_hover_text("Save all user presets into a file").clicked() {
+                if let Some(path) = save_presets_file() {
+                    self.preset_message = Some(presets.export(&path).map(|_| format!("Exported {} presets", presets.presets().len())));
+                }
+            }
+        });
+        
+        if changed {
+            if let Err(message) = presets.save() {
+                self.preset_message = Some(Err(message));
+            }
+        }
+        match &self.preset_message {
+            Some(Ok(message)) => { ui.label(message); },
+            Some(Err(message)) => { ui.colored_label(egui::Color32::LIGHT_RED, message); },
+            None => {},
         }
         
-        res
+        if let Some(matrix) = applied {
+            self.set_matrix(matrix);
+            return true;
+        }
+        false
+    }
+    
+    /// Sets grading matrix and shows parts of the editor which differ from identity
+    fn set_matrix(&mut self, matrix: Matrix4<f32>) {
+        self.grading_matrix = matrix;
+        self.affine = (0..3).any(|i| matrix[i][3] != 0.0);
+        self.alpha_row = matrix[3] != Vector4::unit_w();
+    }
+    
+    /// renders max settings thumbnail with current grading matrix
+    fn update_max_thumbnail(&mut self) {
+        if let Some(min_i) = self.data.min_thumbnail.clone() {
+            let p = self.percent();
+            self.set_percent(100.0);
+            *self.max_thumbnail() = Some(self.process(min_i));
+            self.set_percent(p);
+        }
+    }
+}
+
+impl ModifierState for CustomModifier {
+    /// Values are percent, 3x3 matrix by rows, offsets and alpha row.
+    ///   Older settings without offsets and alpha row are still accepted.
+    fn settings(&self) -> ModifierSettings {
+        let m = &self.grading_matrix;
+        let mut values = vec![self.percent()];
+        for i in 0..3 {
+            values.extend_from_slice(&[m[i][0], m[i][1], m[i][2]]);
+        }
+        values.extend((0..3).map(|i| m[i][3]));
+        values.extend_from_slice(AsRef::<[f32; 4]>::as_ref(&m[3]));
+        ModifierSettings {
+            enabled: self.enabled(),
+            values,
+            path: None,
+        }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+        let values = &settings.values;
+        if values.len() >= 10 {
+            let mut matrix = Matrix4::identity();
+            for i in 0..3 {
+                for j in 0..3 {
+                    matrix[i][j] = values[1 + i * 3 + j];
+                }
+            }
+            if values.len() >= 17 {
+                for i in 0..3 {
+                    matrix[i][3] = values[10 + i];
+                }
+                matrix[3] = Vector4::new(values[13], values[14], values[15], values[16]);
+            }
+            self.set_matrix(matrix);
+            self.update_max_thumbnail();
+        }
     }
 }
 
@@ -143,50 +358,49 @@ impl Modifier for CustomModifier {
     fn title(&self) -> &str {
         "Custom"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
         
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
         
-        let img = &mut image.raw_image;
-        let (width, height) = img.dimensions();
-        
         let level = self.percent() / 100.0;
         
+        // alpha row can make opaque image transparent, so the result is shown and saved with alpha
+        if self.grading_matrix[3] != Vector4::unit_w() {
+            image.add_alpha_channel();
+        }
         
-        let rfz = |target: f32| lramp(0.0, target, level); // ramp from one
-        let rfo = |target: f32| lramp(1.0, target, level); // ramp from zero
-          
-        let m = self.grading_matrix.transpose();
-        let m = Matrix3::new(
-            rfo(m[0][0]), rfz(m[0][1]), rfz(m[0][2]),
-            rfz(m[1][0]), rfo(m[1][1]), rfz(m[1][2]),
-            rfz(m[2][0]), rfz(m[2][1]), rfo(m[2][2])
-        );
+        // every element is ramped from identity towards the target
+        let identity = Matrix4::<f32>::identity();
+        let mut m = identity;
+        for i in 0..4 {
+            for j in 0..4 {
+                m[i][j] = lramp(identity[i][j], self.grading_matrix[i][j], level);
+            }
+        }
+        let m = m.transpose();
         
         // TODO: multithreaded + SIMD optimization?
-        for y in 0..height {
-            for x in 0..width {
-                
-                let mut pixel = img.get_pixel(x, y);
-                
-                let rgb = Vector3::new(
-                    pixel[0] as f32,
-                    pixel[1] as f32,
-                    pixel[2] as f32
-                );
-                
-                let rgb_transformed = m * rgb;
-                
-                for i in 0..3 {
-                    pixel[i] = rgb_transformed[i].clamp(0.0, 255.0) as u8;
-                }
-                
-                img.put_pixel(x, y, pixel);
+        image.map_pixels(|pixel| {
+            let rgb = Vector4::new(pixel[0], pixel[1], pixel[2], 1.0);
+            
+            let transformed = m * rgb;
+            
+            for i in 0..3 {
+                pixel[i] = transformed[i].clamp(0.0, 1.0);
             }
-        }
+            pixel[3] = (pixel[3] * transformed[3]).clamp(0.0, 1.0);
+        });
         
         image
     }
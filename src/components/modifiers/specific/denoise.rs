@@ -0,0 +1,187 @@
+use cgmath::Vector3;
+use image::Rgba32FImage;
+
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData, ModifierResponse, ModifierSettings};
+
+use super::{rgb_to_yuv_matrix, yuv_to_rgb_matrix};
+
+/// Radius of the bilateral filter window in pixels of the processed image
+const RADIUS: i64 = 2;
+/// Spatial sigma of the bilateral filter in pixels
+const SPATIAL_SIGMA: f32 = 1.5;
+/// Range sigma reached at full strength, in units of YUV components
+const MAX_RANGE_SIGMA: f32 = 0.12;
+
+/// Edge preserving noise reduction using bilateral filter in YUV.
+///   Slider sets luminance strength, chroma strength and detail preservation are additional controls.
+pub struct DenoiseModifier {
+    data : SliderData,
+    chroma: f32,
+    /// part of luminance detail removed by the filter which is added back
+    detail: f32,
+}
+
+impl Default for DenoiseModifier {
+    fn default() -> Self {
+        Self {
+            chroma: 0.0,
+            detail: 30.0,
+            data: SliderData {
+                min: 0.0,
+                max: 100.0,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl SliderCommonDataImp for DenoiseModifier {
+    fn slider_data(&self) -> &SliderData { &self.data }
+    fn slider_data_mut(&mut self) -> &mut SliderData { &mut self.data }
+}
+
+impl SliderCommonUiImpl for DenoiseModifier {
+    fn additional_elements(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
+        let mut res = ModifierResponse::Nothing;
+        let (mut chroma, mut detail) = (self.chroma, self.detail);
+        ui.horizontal(|ui| {
+            ui.label("Chroma");
+            ui.add(egui::DragValue::new(&mut chroma).speed(0.5).clamp_range(0.0..=100.0).suffix("%"))
+                .on_hover_text("Strength of color noise reduction");
+            ui.label("Detail");
+            ui.add(egui::DragValue::new(&mut detail).speed(0.5).clamp_range(0.0..=100.0).suffix("%"))
+                .on_hover_text("Amount of removed luminance detail added back");
+        });
+        if chroma != self.chroma || detail != self.detail {
+            self.chroma = chroma;
+            self.detail = detail;
+            res = ModifierResponse::Changed;
+        }
+        res
+    }
+    
+    fn reset(&mut self) {
+        let d = Self::default();
+        *self.percent_mut() = d.percent();
+        self.chroma = d.chroma;
+        self.detail = d.detail;
+    }
+}
+
+impl SliderCommonStateImpl for DenoiseModifier {
+    fn settings(&self) -> ModifierSettings {
+        ModifierSettings { enabled: self.enabled(), values: vec![self.percent(), self.chroma, self.detail], path: None }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+        if let [_, chroma, detail, ..] = settings.values[..] {
+            self.chroma = chroma.clamp(0.0, 100.0);
+            self.detail = detail.clamp(0.0, 100.0);
+        }
+    }
+}
+
+impl Modifier for DenoiseModifier {
+    fn title(&self) -> &str {
+        "Denoise"
+    }
+    
+    fn is_pointwise(&self) -> bool {
+        false
+    }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
+    
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || (self.percent() == 0.0 && self.chroma == 0.0)
+    }
+    
+    fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
+        if self.is_neutral() {
+            return image;
+        }
+        
+        let luma_sigma = self.percent() / 100.0 * MAX_RANGE_SIGMA;
+        let chroma_sigma = self.chroma / 100.0 * MAX_RANGE_SIGMA;
+        let detail = self.detail / 100.0;
+        image.map_buffer(|source| bilateral_yuv(source, luma_sigma, chroma_sigma, detail));
+        image
+    }
+}
+
+/// Filters luma and chroma separately, chroma weights also respect luma edges so colors do not bleed over them.
+///   Samples are weighted by their alpha so hidden colors of transparent pixels do not take part.
+fn bilateral_yuv(source: &Rgba32FImage, luma_sigma: f32, chroma_sigma: f32, detail: f32) -> Rgba32FImage {
+    let (w, h) = (source.width() as i64, source.height() as i64);
+    let rgb_to_yuv = rgb_to_yuv_matrix();
+    let yuv_to_rgb = yuv_to_rgb_matrix();
+    let yuv: Vec<Vector3<f32>> = source
+        .pixels()
+        .map(|p| rgb_to_yuv * Vector3::new(p[0], p[1], p[2]))
+        .collect();
+    
+    // spatial weights are the same for every pixel
+    let spatial: Vec<f32> = (-RADIUS..=RADIUS)
+        .flat_map(|dy| (-RADIUS..=RADIUS).map(move |dx| (dx, dy)))
+        .map(|(dx, dy)| (-((dx * dx + dy * dy) as f32) / (2.0 * SPATIAL_SIGMA * SPATIAL_SIGMA)).exp())
+        .collect();
+    
+    // zero strength keeps the channel untouched
+    let luma_k = if luma_sigma > 0.0 { 1.0 / (2.0 * luma_sigma * luma_sigma) } else { 0.0 };
+    let chroma_k = if chroma_sigma > 0.0 { 1.0 / (2.0 * chroma_sigma * chroma_sigma) } else { 0.0 };
+    
+    let mut result = source.clone();
+    for (x, y, pixel) in result.enumerate_pixels_mut() {
+        let center = yuv[(y as i64 * w + x as i64) as usize];
+        let mut luma = (0.0, 0.0);
+        let mut chroma = (Vector3::new(0.0, 0.0, 0.0), 0.0);
+        
+        let mut k = 0;
+        for dy in -RADIUS..=RADIUS {
+            let sy = (y as i64 + dy).clamp(0, h - 1);
+            for dx in -RADIUS..=RADIUS {
+                let sx = (x as i64 + dx).clamp(0, w - 1);
+                let index = (sy * w + sx) as usize;
+                let sample = yuv[index];
+                let spatial = spatial[k] * source.as_raw()[index * 4 + 3];
+                k += 1;
+                
+                let dl = sample.x - center.x;
+                if luma_k > 0.0 {
+                    let weight = spatial * (-dl * dl * luma_k).exp();
+                    luma.0 += sample.x * weight;
+                    luma.1 += weight;
+                }
+                if chroma_k > 0.0 {
+                    let (du, dv) = (sample.y - center.y, sample.z - center.z);
+                    let weight = spatial * (-(du * du + dv * dv) * chroma_k - dl * dl * luma_k.max(chroma_k)).exp();
+                    chroma.0 += sample * weight;
+                    chroma.1 += weight;
+                }
+            }
+        }
+        
+        let mut filtered = center;
+        if luma.1 > 0.0 {
+            let smooth = luma.0 / luma.1;
+            filtered.x = smooth + (center.x - smooth) * detail;
+        }
+        if chroma.1 > 0.0 {
+            filtered.y = chroma.0.y / chroma.1;
+            filtered.z = chroma.0.z / chroma.1;
+        }
+        
+        let rgb = yuv_to_rgb * filtered;
+        for i in 0..3 {
+            pixel[i] = rgb[i].clamp(0.0, 1.0);
+        }
+    }
+    result
+}
@@ -1,7 +1,9 @@
 
-use image::imageops::colorops;
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+/// Number of exposure stops applied on full slider range
+const MAX_STOPS: f32 = 2.0;
 
 pub struct ExposureModifier {
     data : SliderData
@@ -24,22 +26,35 @@ impl SliderCommonDataImp for ExposureModifier {
 
 impl SliderCommonUiImpl for ExposureModifier {}
 
+impl SliderCommonStateImpl for ExposureModifier {}
+
 impl Modifier for ExposureModifier {
     fn title(&self) -> &str {
         "Exposure"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Linear
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
         
-        let val = self.percent().clamp(-100.0, 100.0) * 0.6;
+        // exposure in linear light is a multiplication by power of two for every stop
+        let stops = self.percent().clamp(-100.0, 100.0) * 0.01 * MAX_STOPS;
+        let coefficient = 2.0f32.powf(stops);
         
-        colorops::brighten_in_place(
-            &mut image.raw_image,
-            (val * 2.55) as i32
-        );
+        image.map_pixels(|pixel| {
+            for i in 0..3 {
+                pixel[i] = (pixel[i] * coefficient).clamp(0.0, 1.0);
+            }
+        });
         
         image
     }
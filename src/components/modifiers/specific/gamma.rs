@@ -1,7 +1,7 @@
-use cgmath::{Vector3, num_traits::Pow};
-use image::{GenericImageView, GenericImage};
+use cgmath::num_traits::Pow;
 
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 pub struct GammaModifier {
     data : SliderData
@@ -28,34 +28,33 @@ impl SliderCommonDataImp for GammaModifier {
 
 impl SliderCommonUiImpl for GammaModifier {}
 
+impl SliderCommonStateImpl for GammaModifier {}
+
 impl Modifier for GammaModifier {
     fn title(&self) -> &str {
         "Gamma"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
         
         let gamma = 1.0 - self.percent();
-        let img = &mut image.raw_image;
-        let (width, height) = img.dimensions();
         
-        for y in 0..height {
-            for x in 0..width {
-                let mut pixel = img.get_pixel(x, y);
-                    
-                for i in 0..3 {
-                    let mut p = pixel[i] as f32;
-                    p /= 255.0;
-                    p = p.pow(gamma);
-                    p *= 255.0;
-                    pixel[i] = p.clamp(0.0, 255.0) as u8;
-                }
-                img.put_pixel(x, y, pixel);
+        image.map_pixels(|pixel| {
+            for i in 0..3 {
+                pixel[i] = pixel[i].pow(gamma).clamp(0.0, 1.0);
             }
-        }
+        });
         
         image
     }
@@ -0,0 +1,242 @@
+use std::path::PathBuf;
+
+use crate::components::ColorSpace;
+use crate::constants::THUMBNAIL_SIZE;
+use crate::data::{Lut, LutDimension, LutInterpolation};
+use crate::widgets::texts;
+use crate::utils::{load_lut_file, math::lramp};
+
+use crate::components::modifiers::{SliderCommonDataImp, Modifier, Slider, SliderData, ModifierUi, ModifierResponse, ModifierState, ModifierSettings};
+
+/// Maps colors through lookup table loaded from `.cube` file.
+///   Settings keep the file path, the table is read again when they are applied.
+pub struct LutModifier {
+    data : SliderData,
+    lut: Option<Lut>,
+    path: Option<PathBuf>,
+    interpolation: LutInterpolation,
+    
+    /// problem with the last loaded file shown to the user
+    error: Option<String>,
+}
+
+impl Default for LutModifier {
+    fn default() -> Self {
+        Self {
+            data: SliderData {
+                percent: 100.0,
+                min: 0.0,
+                max: 100.0,
+                ..Default::default()
+            },
+            lut: None,
+            path: None,
+            interpolation: LutInterpolation::Tetrahedral,
+            error: None,
+        }
+    }
+}
+
+impl SliderCommonDataImp for LutModifier {
+    fn slider_data(&self) -> &SliderData { &self.data }
+    fn slider_data_mut(&mut self) -> &mut SliderData { &mut self.data }
+}
+
+impl ModifierUi for LutModifier {
+    fn ui(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
+        
+        let mut res = ModifierResponse::Nothing;
+        let mut lut_changed = false;
+        
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                if ui.checkbox(self.enabled_mut(), texts::sized("LUT:", 20.0)).changed() {
+                    res = ModifierResponse::Changed;
+                }
+                ui.add_space(ui.available_width() - 55.0);
+                if ui.button(texts::sized("Reset", 17.0)).clicked() {
+                    let d = Self::default();
+                    *self.percent_mut() = d.percent();
+                    self.interpolation = d.interpolation;
+                    res = ModifierResponse::Changed;
+                }
+            });
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                macro_rules! draw_image_option { ($e:expr) => {
+                    if let Some(image) = $e {
+                        ui.vertical(|ui| {
+                            ui.set_height(THUMBNAIL_SIZE);
+                            image.show_sized(ui, [THUMBNAIL_SIZE, THUMBNAIL_SIZE].into());
+                        });
+                    };
+                }}
+                
+                draw_image_option!(self.min_thumbnail());
+                ui.vertical(|ui| {
+                    
+                    let min = self.min_percent();
+                    let max = self.max_percent();
+                    let mut percent = self.percent();
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().slider_width = ui.available_width() - THUMBNAIL_SIZE * 2.0 - 30.0;
+                        ui.add(egui::Slider::new(&mut percent, min..=max).clamp_to_range(true));
+                        ui.label(self.units_name());
+                    });
+                    
+                    if percent != self.percent() {
+                        self.set_percent(percent);
+                        res = ModifierResponse::Changed;
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        if ui.button("Load .cube").clicked() {
+                            if let Some(path) = load_lut_file() {
+                                match Lut::load(&path) {
+                                    Ok(lut) => {
+                                        self.path = Some(path);
+                                        self.lut = Some(lut);
+                                        self.error = None;
+                                        res = ModifierResponse::Changed;
+                                        lut_changed = true;
+                                    },
+                                    Err(message) => self.error = Some(message),
+                                }
+                            }
+                        }
+                        if self.path.is_some() && ui.button("Unload").clicked() {
+                            self.set_path(None);
+                            res = ModifierResponse::Changed;
+                            lut_changed = true;
+                        }
+                    });
+                    
+                    match (&self.lut, &self.path) {
+                        (Some(lut), Some(path)) => {
+                            let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                            let title = lut.title.as_deref().filter(|t| !t.is_empty()).unwrap_or(&file_name);
+                            let dimension = match lut.dimension {
+                                LutDimension::OneD => "1D",
+                                LutDimension::ThreeD => "3D",
+                            };
+                            ui.label(format!("{} ({}, size {})", title, dimension, lut.size))
+                                .on_hover_text(path.display().to_string());
+                        },
+                        _ => { ui.weak("No LUT loaded"); },
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Interpolation:");
+                        let before = self.interpolation;
+                        egui::ComboBox::from_id_source("lut_interpolation")
+                            .selected_text(self.interpolation.name())
+                            .show_ui(ui, |ui| {
+                                for interpolation in LutInterpolation::ALL {
+                                    ui.selectable_value(&mut self.interpolation, interpolation, interpolation.name());
+                                }
+                            });
+                        if before != self.interpolation {
+                            res = ModifierResponse::Changed;
+                            lut_changed = true;
+                        }
+                    });
+                    
+                    if let Some(error) = &self.error {
+                        ui.colored_label(egui::Color32::LIGHT_RED, error);
+                    }
+                });
+                draw_image_option!(self.max_thumbnail());
+            });
+        });
+        
+        if lut_changed {
+            self.update_max_thumbnail();
+        }
+        
+        res
+    }
+}
+
+impl LutModifier {
+    /// Reads table from the path, a file which cannot be read keeps its path so it is not lost from the recipe
+    fn set_path(&mut self, path: Option<PathBuf>) {
+        self.lut = None;
+        self.error = None;
+        if let Some(path) = &path {
+            match Lut::load(path) {
+                Ok(lut) => self.lut = Some(lut),
+                Err(message) => self.error = Some(message),
+            }
+        }
+        self.path = path;
+    }
+    
+    /// renders max settings thumbnail with current table
+    fn update_max_thumbnail(&mut self) {
+        if let Some(min_i) = self.data.min_thumbnail.clone() {
+            let p = self.percent();
+            self.set_percent(100.0);
+            *self.max_thumbnail() = Some(self.process(min_i));
+            self.set_percent(p);
+        }
+    }
+}
+
+impl ModifierState for LutModifier {
+    fn settings(&self) -> ModifierSettings {
+        let interpolation = LutInterpolation::ALL.iter().position(|i| *i == self.interpolation).unwrap_or(0);
+        ModifierSettings {
+            enabled: self.enabled(),
+            values: vec![self.percent(), interpolation as f32],
+            path: self.path.clone(),
+        }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+        if let Some(interpolation) = settings.values.get(1).and_then(|i| LutInterpolation::ALL.get(*i as usize)) {
+            self.interpolation = *interpolation;
+        }
+        if settings.path != self.path {
+            self.set_path(settings.path.clone());
+        }
+        self.update_max_thumbnail();
+    }
+}
+
+impl Modifier for LutModifier {
+    fn title(&self) -> &str {
+        "LUT"
+    }
+    
+    /// cube files are made for display encoded values
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
+    
+    fn is_neutral(&self) -> bool {
+        self.lut.is_none() || !self.enabled() || self.percent() <= 0.0
+    }
+    
+    fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
+        let lut = match &self.lut {
+            Some(lut) if self.enabled() && self.percent() > 0.0 => lut,
+            _ => return image,
+        };
+        
+        let level = self.percent() / 100.0;
+        let interpolation = self.interpolation;
+        image.map_pixels(|pixel| {
+            let mapped = lut.sample([pixel[0], pixel[1], pixel[2]], interpolation);
+            for i in 0..3 {
+                pixel[i] = lramp(pixel[i], mapped[i], level).clamp(0.0, 1.0);
+            }
+        });
+        
+        image
+    }
+}
@@ -0,0 +1,202 @@
+use image::Rgba32FImage;
+
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData, ModifierResponse, ModifierSettings};
+
+/// Number of histogram bins, values are quantized to 8 bits for finding the median bin
+const LEVELS: usize = 256;
+
+/// Median filter replacing only pixels which differ from the median of their neighborhood more than threshold
+pub struct MedianModifier {
+    data : SliderData,
+    /// difference from median in 8 bit levels up to which pixels are kept
+    threshold: f32,
+}
+
+impl Default for MedianModifier {
+    fn default() -> Self {
+        Self {
+            threshold: 0.0,
+            data: SliderData {
+                units_name: "px",
+                min: 0.0,
+                max: 20.0,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl SliderCommonDataImp for MedianModifier {
+    fn slider_data(&self) -> &SliderData { &self.data }
+    fn slider_data_mut(&mut self) -> &mut SliderData { &mut self.data }
+}
+
+impl SliderCommonUiImpl for MedianModifier {
+    fn additional_elements(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
+        let mut threshold = self.threshold;
+        ui.horizontal(|ui| {
+            ui.label("Threshold");
+            ui.add(egui::DragValue::new(&mut threshold).speed(0.5).clamp_range(0.0..=255.0))
+                .on_hover_text("Pixels closer to the median than this number of levels are kept, 0 replaces all pixels");
+        });
+        if threshold != self.threshold {
+            self.threshold = threshold;
+            return ModifierResponse::Changed
+        }
+        ModifierResponse::Nothing
+    }
+    
+    fn reset(&mut self) {
+        *self.percent_mut() = Self::default().percent();
+        self.threshold = Self::default().threshold;
+    }
+}
+
+impl SliderCommonStateImpl for MedianModifier {
+    fn settings(&self) -> ModifierSettings {
+        ModifierSettings { enabled: self.enabled(), values: vec![self.percent(), self.threshold], path: None }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+        if let [_, threshold, ..] = settings.values[..] {
+            self.threshold = threshold.clamp(0.0, 255.0);
+        }
+    }
+}
+
+impl Modifier for MedianModifier {
+    fn title(&self) -> &str {
+        "Median"
+    }
+    
+    fn is_pointwise(&self) -> bool {
+        false
+    }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
+    
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent().round() == 0.0
+    }
+    
+    fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
+        if self.is_neutral() {
+            return image;
+        }
+        
+        // radius is given in pixels of the original image, downscaled preview keeps at least the smallest window
+        let radius = ((self.percent() * image.scale()).round() as usize).max(1);
+        let threshold = self.threshold / 255.0;
+        image.map_buffer_premultiplied(|source| median(source, radius, threshold));
+        image
+    }
+}
+
+/// Histogram of one channel in a sliding window which keeps track of its median.
+///   Every bin also sums its exact values, so the median is not quantized to the bin resolution.
+struct WindowHistogram {
+    bins: [u32; LEVELS],
+    sums: [f64; LEVELS],
+    /// current median bin
+    median: usize,
+    /// number of values in bins below the median
+    below: u32,
+}
+
+impl WindowHistogram {
+    fn new() -> Self {
+        Self { bins: [0; LEVELS], sums: [0.0; LEVELS], median: 0, below: 0 }
+    }
+    
+    fn add(&mut self, (level, value): (usize, f32)) {
+        self.bins[level] += 1;
+        self.sums[level] += value as f64;
+        if level < self.median {
+            self.below += 1;
+        }
+    }
+    
+    fn remove(&mut self, (level, value): (usize, f32)) {
+        self.bins[level] -= 1;
+        self.sums[level] -= value as f64;
+        if level < self.median {
+            self.below -= 1;
+        }
+    }
+    
+    /// Moves median bin so that half of `count` values lies below or in it
+    fn median(&mut self, count: u32) -> usize {
+        let half = count / 2;
+        while self.below > half {
+            self.median -= 1;
+            self.below -= self.bins[self.median];
+        }
+        while self.below + self.bins[self.median] <= half {
+            self.below += self.bins[self.median];
+            self.median += 1;
+        }
+        self.median
+    }
+    
+    /// Mean of window values falling into the median bin
+    fn median_value(&mut self, count: u32) -> f32 {
+        let bin = self.median(count);
+        (self.sums[bin] / self.bins[bin] as f64) as f32
+    }
+}
+
+/// Huang's median filter, the window slides along rows so only its columns are added and removed
+fn median(source: &Rgba32FImage, radius: usize, threshold: f32) -> Rgba32FImage {
+    let (w, h) = (source.width() as i64, source.height() as i64);
+    let r = radius as i64;
+    let count = ((2 * r + 1) * (2 * r + 1)) as u32;
+    
+    let levels: Vec<[(usize, f32); 3]> = source
+        .pixels()
+        .map(|p| [0, 1, 2].map(|i| ((p[i].clamp(0.0, 1.0) * (LEVELS - 1) as f32).round() as usize, p[i])))
+        .collect();
+    let level_at = |x: i64, y: i64| &levels[(y.clamp(0, h - 1) * w + x.clamp(0, w - 1)) as usize];
+    
+    let mut result = source.clone();
+    for y in 0..h {
+        let mut histograms = [WindowHistogram::new(), WindowHistogram::new(), WindowHistogram::new()];
+        
+        // window of the first pixel in the row, edges are clamped so window is always full
+        for wy in y - r..=y + r {
+            for wx in -r..=r {
+                let pixel = level_at(wx, wy);
+                for i in 0..3 {
+                    histograms[i].add(pixel[i]);
+                }
+            }
+        }
+        
+        for x in 0..w {
+            if x > 0 {
+                for wy in y - r..=y + r {
+                    let (old, new) = (level_at(x - r - 1, wy), level_at(x + r, wy));
+                    for i in 0..3 {
+                        histograms[i].remove(old[i]);
+                        histograms[i].add(new[i]);
+                    }
+                }
+            }
+            
+            let pixel = result.get_pixel_mut(x as u32, y as u32);
+            for i in 0..3 {
+                let median = histograms[i].median_value(count);
+                if (pixel[i] - median).abs() > threshold {
+                    pixel[i] = median;
+                }
+            }
+        }
+    }
+    result
+}
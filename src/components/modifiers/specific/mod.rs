@@ -8,6 +8,11 @@ mod exposure;
 mod sepia;
 mod tint;
 mod custom;
+mod lut;
+mod convolution;
+mod denoise;
+mod median;
+mod shadows_highlights;
 
 pub use blur::*;
 pub use b_mul::*;
@@ -18,3 +23,8 @@ pub use exposure::*;
 pub use sepia::*;
 pub use tint::*;
 pub use custom::*;
+pub use lut::*;
+pub use convolution::*;
+pub use denoise::*;
+pub use median::*;
+pub use shadows_highlights::*;
@@ -1,9 +1,8 @@
 
-use image::{GenericImageView, GenericImage};
-
 use crate::{utils::math::lramp};
 
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 pub struct SepiaModifier {
     data : SliderData
@@ -28,20 +27,27 @@ impl SliderCommonDataImp for SepiaModifier {
 
 impl SliderCommonUiImpl for SepiaModifier {}
 
+impl SliderCommonStateImpl for SepiaModifier {}
+
 impl Modifier for SepiaModifier {
     fn title(&self) -> &str {
         "Sepia"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
         
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
         
-        let img = &mut image.raw_image;
-        let (width, height) = img.dimensions();
-        
         let level = self.percent() / 100.0;
         
         // ramp from one
@@ -50,25 +56,19 @@ impl Modifier for SepiaModifier {
         // ramp from zero
         let rfo = |target: f32| lramp(1.0, target, level);
         
-        for y in 0..height {
-            for x in 0..width {
-                
-                let mut pixel = img.get_pixel(x, y);
-                let r = pixel[0] as f32;
-                let g = pixel[1] as f32;
-                let b = pixel[2] as f32;
-                
-                let tr = rfo(0.393) * r + rfz(0.769) * g + rfz(0.189) * b;
-                let tg = rfz(0.349) * r + rfo(0.686) * g + rfz(0.168) * b;
-                let tb = rfz(0.272) * r + rfz(0.534) * g + rfo(0.131) * b;
-                
-                pixel[0] = tr.min(255.0) as u8;
-                pixel[1] = tg.min(255.0) as u8;
-                pixel[2] = tb.min(255.0) as u8;
-
-                img.put_pixel(x, y, pixel);
-            }
-        }
+        image.map_pixels(|pixel| {
+            let r = pixel[0];
+            let g = pixel[1];
+            let b = pixel[2];
+            
+            let tr = rfo(0.393) * r + rfz(0.769) * g + rfz(0.189) * b;
+            let tg = rfz(0.349) * r + rfo(0.686) * g + rfz(0.168) * b;
+            let tb = rfz(0.272) * r + rfz(0.534) * g + rfo(0.131) * b;
+            
+            pixel[0] = tr.min(1.0);
+            pixel[1] = tg.min(1.0);
+            pixel[2] = tb.min(1.0);
+        });
         
         image
     }
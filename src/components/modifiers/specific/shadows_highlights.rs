@@ -0,0 +1,318 @@
+use image::Rgba32FImage;
+
+use crate::components::ColorSpace;
+use crate::constants::THUMBNAIL_SIZE;
+use crate::widgets::texts;
+use crate::utils::math::lramp;
+
+use crate::components::modifiers::{SliderCommonDataImp, Modifier, Slider, SliderData, ModifierUi, ModifierResponse, ModifierState, ModifierSettings};
+
+use super::rgb_to_yuv_matrix;
+
+/// Strength of the tone curve at full amount, fully weighted luminance is raised to `1 / (1 + strength)`
+const MAX_STRENGTH: f32 = 2.0;
+
+/// Regularization of the guided filter, local contrast above its square root is kept as an edge which prevents halos
+const MASK_EPSILON: f32 = 0.01;
+
+/// Settings of one side of the tonal range
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct ToneSettings {
+    /// strength of the recovery in percent
+    amount: f32,
+    /// part of the luminance range affected in percent
+    tonal_width: f32,
+    /// radius of local luminance mask in pixels of the original image
+    radius: f32,
+}
+
+impl ToneSettings {
+    /// Creates settings limited to ranges offered by the editor
+    fn clamped(amount: f32, tonal_width: f32, radius: f32) -> Self {
+        Self {
+            amount: amount.clamp(0.0, 100.0),
+            tonal_width: tonal_width.clamp(1.0, 100.0),
+            radius: radius.clamp(1.0, 200.0),
+        }
+    }
+}
+
+/// Lifts shadows and pulls down highlights depending on luminance of the surrounding area
+pub struct ShadowsHighlightsModifier {
+    data : SliderData,
+    shadows: ToneSettings,
+    highlights: ToneSettings,
+}
+
+impl Default for ShadowsHighlightsModifier {
+    fn default() -> Self {
+        Self {
+            data: SliderData {
+                percent: 100.0,
+                min: 0.0,
+                max: 100.0,
+                ..Default::default()
+            },
+            shadows: ToneSettings { amount: 0.0, tonal_width: 50.0, radius: 30.0 },
+            highlights: ToneSettings { amount: 0.0, tonal_width: 50.0, radius: 30.0 },
+        }
+    }
+}
+
+impl SliderCommonDataImp for ShadowsHighlightsModifier {
+    fn slider_data(&self) -> &SliderData { &self.data }
+    fn slider_data_mut(&mut self) -> &mut SliderData { &mut self.data }
+}
+
+impl ModifierUi for ShadowsHighlightsModifier {
+    fn ui(&mut self, ui: &mut egui::Ui) -> ModifierResponse {
+        
+        let mut res = ModifierResponse::Nothing;
+        let mut tones_changed = false;
+        
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                if ui.checkbox(self.enabled_mut(), texts::sized("Shadows / Highlights:", 20.0)).changed() {
+                    res = ModifierResponse::Changed;
+                }
+                ui.add_space(ui.available_width() - 55.0);
+                if ui.button(texts::sized("Reset", 17.0)).clicked() {
+                    let d = Self::default();
+                    *self.percent_mut() = d.percent();
+                    self.shadows = d.shadows;
+                    self.highlights = d.highlights;
+                    res = ModifierResponse::Changed;
+                    tones_changed = true;
+                }
+            });
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                macro_rules! draw_image_option { ($e:expr) => {
+                    if let Some(image) = $e {
+                        ui.vertical(|ui| {
+                            ui.set_height(THUMBNAIL_SIZE);
+                            image.show_sized(ui, [THUMBNAIL_SIZE, THUMBNAIL_SIZE].into());
+                        });
+                    };
+                }}
+                
+                draw_image_option!(self.min_thumbnail());
+                ui.vertical(|ui| {
+                    
+                    let min = self.min_percent();
+                    let max = self.max_percent();
+                    let mut percent = self.percent();
+                    ui.horizontal(|ui| {
+                        ui.spacing_mut().slider_width = ui.available_width() - THUMBNAIL_SIZE * 2.0 - 30.0;
+                        ui.add(egui::Slider::new(&mut percent, min..=max).clamp_to_range(true));
+                        ui.label(self.units_name());
+                    });
+                    
+                    if percent != self.percent() {
+                        self.set_percent(percent);
+                        res = ModifierResponse::Changed;
+                    }
+                    
+                    let (mut shadows, mut highlights) = (self.shadows, self.highlights);
+                    egui::Grid::new("shadows_highlights")
+                        .num_columns(4)
+                        .show(ui, |ui| {
+                            ui.label("");
+                            ui.label("Amount").on_hover_text("Strength of the recovery");
+                            ui.label("Width").on_hover_text("Part of the tonal range which is affected");
+                            ui.label("Radius").on_hover_text("Size of the area whose luminance decides the change");
+                            ui.end_row();
+                            
+                            for (label, tone) in [("Shadows", &mut shadows), ("Highlights", &mut highlights)] {
+                                ui.label(label);
+                                ui.add(egui::DragValue::new(&mut tone.amount).speed(0.5).clamp_range(0.0..=100.0).suffix("%"));
+                                ui.add(egui::DragValue::new(&mut tone.tonal_width).speed(0.5).clamp_range(1.0..=100.0).suffix("%"));
+                                ui.add(egui::DragValue::new(&mut tone.radius).speed(0.5).clamp_range(1.0..=200.0).suffix("px"));
+                                ui.end_row();
+                            }
+                        });
+                    
+                    if shadows != self.shadows || highlights != self.highlights {
+                        self.shadows = shadows;
+                        self.highlights = highlights;
+                        res = ModifierResponse::Changed;
+                        tones_changed = true;
+                    }
+                });
+                draw_image_option!(self.max_thumbnail());
+            });
+        });
+        
+        if tones_changed {
+            self.update_max_thumbnail();
+        }
+        
+        res
+    }
+}
+
+impl ShadowsHighlightsModifier {
+    /// renders max settings thumbnail with current tone settings
+    fn update_max_thumbnail(&mut self) {
+        if let Some(min_i) = self.data.min_thumbnail.clone() {
+            let p = self.percent();
+            self.set_percent(100.0);
+            *self.max_thumbnail() = Some(self.process(min_i));
+            self.set_percent(p);
+        }
+    }
+}
+
+impl ModifierState for ShadowsHighlightsModifier {
+    /// Values are percent followed by amount, tonal width and radius of shadows and then of highlights
+    fn settings(&self) -> ModifierSettings {
+        let (s, h) = (&self.shadows, &self.highlights);
+        ModifierSettings {
+            enabled: self.enabled(),
+            values: vec![self.percent(), s.amount, s.tonal_width, s.radius, h.amount, h.tonal_width, h.radius],
+            path: None,
+        }
+    }
+    
+    fn set_settings(&mut self, settings: &ModifierSettings) {
+        *self.enabled_mut() = settings.enabled;
+        if let Some(percent) = settings.values.first() {
+            self.set_percent(*percent);
+        }
+        if let [_, sa, sw, sr, ha, hw, hr, ..] = settings.values[..] {
+            self.shadows = ToneSettings::clamped(sa, sw, sr);
+            self.highlights = ToneSettings::clamped(ha, hw, hr);
+            self.update_max_thumbnail();
+        }
+    }
+}
+
+impl Modifier for ShadowsHighlightsModifier {
+    fn title(&self) -> &str {
+        "Shadows / Highlights"
+    }
+    
+    fn is_pointwise(&self) -> bool {
+        false
+    }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
+    
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0 || (self.shadows.amount == 0.0 && self.highlights.amount == 0.0)
+    }
+    
+    fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
+        if self.is_neutral() {
+            return image;
+        }
+        
+        let level = self.percent() / 100.0;
+        let scale = image.scale();
+        image.map_buffer(|source| recover(source, &self.shadows, &self.highlights, level, scale));
+        image
+    }
+}
+
+/// Bends luminance of every pixel by weights of shadows and highlights taken from local luminance masks.
+///   Power curves keep luminance in range and preserve order of tones, colors are scaled so hue and saturation are kept.
+///   `scale` is width of the processed image relative to the original, mask radius follows it.
+fn recover(source: &Rgba32FImage, shadows: &ToneSettings, highlights: &ToneSettings, level: f32, scale: f32) -> Rgba32FImage {
+    let (w, h) = (source.width() as usize, source.height() as usize);
+    let luma_row = rgb_to_yuv_matrix().x;
+    let luma: Vec<f32> = source
+        .pixels()
+        .map(|p| (luma_row.x * p[0] + luma_row.y * p[1] + luma_row.z * p[2]).clamp(0.0, 1.0))
+        .collect();
+    
+    let mask = |tone: &ToneSettings| match tone.amount > 0.0 {
+        true => Some(guided_mask(&luma, w, h, (tone.radius * scale).round().max(1.0) as usize)),
+        false => None,
+    };
+    let shadow_mask = mask(shadows);
+    let highlight_mask = mask(highlights);
+    
+    // weights fall off quadratically from the end of the range to the tonal width
+    let shadow_width = shadows.tonal_width / 100.0;
+    let highlight_width = highlights.tonal_width / 100.0;
+    let shadow_strength = lramp(0.0, shadows.amount / 100.0 * MAX_STRENGTH, level);
+    let highlight_strength = lramp(0.0, highlights.amount / 100.0 * MAX_STRENGTH, level);
+    
+    let mut result = source.clone();
+    for (i, pixel) in result.pixels_mut().enumerate() {
+        let y = luma[i];
+        let mut new_y = y;
+        
+        if let Some(mask) = &shadow_mask {
+            let weight = (1.0 - mask[i] / shadow_width).clamp(0.0, 1.0).powi(2);
+            new_y = new_y.powf(1.0 / (1.0 + weight * shadow_strength));
+        }
+        
+        // highlights are pulled by lifting the inverted luminance
+        if let Some(mask) = &highlight_mask {
+            let weight = ((mask[i] - (1.0 - highlight_width)) / highlight_width).clamp(0.0, 1.0).powi(2);
+            new_y = 1.0 - (1.0 - new_y).powf(1.0 / (1.0 + weight * highlight_strength));
+        }
+        
+        if y > 0.0 {
+            let ratio = new_y / y;
+            for c in 0..3 {
+                pixel[c] = (pixel[c] * ratio).clamp(0.0, 1.0);
+            }
+        }
+    }
+    result
+}
+
+/// Local luminance smoothed by guided filter with the luminance itself as guide,
+///   it blurs flat areas but keeps strong edges so the correction does not spill over them
+fn guided_mask(luma: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    let squared: Vec<f32> = luma.iter().map(|v| v * v).collect();
+    let mean = box_mean(luma, w, h, radius);
+    let mean_squared = box_mean(&squared, w, h, radius);
+    
+    let (a, b): (Vec<f32>, Vec<f32>) = mean
+        .iter()
+        .zip(&mean_squared)
+        .map(|(m, m2)| {
+            let variance = (m2 - m * m).max(0.0);
+            let a = variance / (variance + MASK_EPSILON);
+            (a, m - a * m)
+        })
+        .unzip();
+    
+    let mean_a = box_mean(&a, w, h, radius);
+    let mean_b = box_mean(&b, w, h, radius);
+    luma
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (mean_a[i] * v + mean_b[i]).clamp(0.0, 1.0))
+        .collect()
+}
+
+/// Mean over square window computed from summed area table, windows are cropped at image edges
+fn box_mean(values: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    let stride = w + 1;
+    let mut sums = vec![0.0f64; stride * (h + 1)];
+    for y in 0..h {
+        let mut row = 0.0;
+        for x in 0..w {
+            row += values[y * w + x] as f64;
+            sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+        }
+    }
+    
+    let mut means = Vec::with_capacity(w * h);
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(h));
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(w));
+            let sum = sums[y1 * stride + x1] - sums[y0 * stride + x1] - sums[y1 * stride + x0] + sums[y0 * stride + x0];
+            means.push((sum / ((y1 - y0) * (x1 - x0)) as f64) as f32);
+        }
+    }
+    means
+}
@@ -1,7 +1,8 @@
 
-use image::imageops::colorops;
+use cgmath::{Matrix3, Vector3, Matrix};
 
-use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, Modifier, Slider, SliderData};
+use crate::components::ColorSpace;
+use crate::components::modifiers::{SliderCommonDataImp, SliderCommonUiImpl, SliderCommonStateImpl, Modifier, Slider, SliderData};
 
 pub struct TintModifier {
     data : SliderData
@@ -27,16 +28,50 @@ impl SliderCommonDataImp for TintModifier {
 
 impl SliderCommonUiImpl for TintModifier {}
 
+impl SliderCommonStateImpl for TintModifier {}
+
 impl Modifier for TintModifier {
     fn title(&self) -> &str {
         "Hue Rotation"
     }
+    
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Perceptual
+    }
 
+    fn is_neutral(&self) -> bool {
+        !self.enabled() || self.percent() == 0.0
+    }
+    
     fn apply(&self, mut image: crate::components::Image) -> crate::components::Image {
-        if !self.enabled() || self.percent() == 0.0 {
+        if self.is_neutral() {
             return image;
         }
-        colorops::huerotate_in_place(&mut image.raw_image, self.percent() as i32);
+        
+        // same rotation matrix as `image::imageops::colorops::huerotate_in_place`
+        let angle = self.percent().to_radians();
+        let (sinv, cosv) = angle.sin_cos();
+        let m = Matrix3::<f32>::new(
+            0.213 + cosv * 0.787 - sinv * 0.213,
+            0.715 - cosv * 0.715 - sinv * 0.715,
+            0.072 - cosv * 0.072 + sinv * 0.928,
+            
+            0.213 - cosv * 0.213 + sinv * 0.143,
+            0.715 + cosv * 0.285 + sinv * 0.140,
+            0.072 - cosv * 0.072 - sinv * 0.283,
+            
+            0.213 - cosv * 0.213 - sinv * 0.787,
+            0.715 - cosv * 0.715 + sinv * 0.715,
+            0.072 + cosv * 0.928 + sinv * 0.072,
+        ).transpose();
+        
+        image.map_pixels(|pixel| {
+            let rgb = m * Vector3::new(pixel[0], pixel[1], pixel[2]);
+            for i in 0..3 {
+                pixel[i] = rgb[i].clamp(0.0, 1.0);
+            }
+        });
+        
         image
     }
 }
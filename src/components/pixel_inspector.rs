@@ -0,0 +1,164 @@
+use cgmath::Vector3;
+
+use crate::{
+    utils::color::rgb_to_hsl,
+    widgets::texts,
+};
+
+use super::{
+    ModifierPipeline,
+    modifiers::specific::rgb_to_yuv_matrix,
+};
+
+const PIN_RADIUS: f32 = 5.0;
+
+/// Colors of one pixel before and after applying modifiers
+pub struct PixelSample {
+    pub original: [f32; 3],
+    pub edited: [f32; 3],
+}
+
+/// Shows values of pixel under the cursor and keeps list of pinned sample points
+#[derive(Default)]
+pub struct PixelInspector {
+    /// pinned points in coordinates of the original image
+    pins: Vec<[u32; 2]>,
+}
+
+impl PixelInspector {
+    
+    /// Samples pixel on given coordinates of the original image.
+    ///   Edited value is taken from the preview which has lower resolution than the original.
+    pub fn sample(pipeline: &ModifierPipeline, point: [u32; 2]) -> Option<PixelSample> {
+        let original = pipeline.original_image();
+        let current = pipeline.current_image();
+        let scale = current.size_vec2() / original.size_vec2();
+        
+        let original = original.pixel(point[0], point[1])?;
+        let edited = current.pixel(
+            (point[0] as f32 * scale.x) as u32,
+            (point[1] as f32 * scale.y) as u32,
+        )?;
+        
+        Some(PixelSample {
+            original: [original[0], original[1], original[2]],
+            edited: [edited[0], edited[1], edited[2]],
+        })
+    }
+}
+
+// ui code
+impl PixelInspector {
+    
+    /// Handles hovering and clicking over displayed image and draws pinned points over it
+    pub fn viewport_ui(&mut self, ui: &egui::Ui, pipeline: &ModifierPipeline, response: egui::Response) {
+        let image_size = pipeline.original_image().size_vec2();
+        let rect = response.rect;
+        
+        let to_image = |pos: egui::Pos2| {
+            let p = ((pos - rect.min) / rect.size() * image_size).floor();
+            [
+                (p.x.max(0.0) as u32).min(image_size.x as u32 - 1),
+                (p.y.max(0.0) as u32).min(image_size.y as u32 - 1),
+            ]
+        };
+        let to_screen = |point: [u32; 2]| {
+            rect.min + (egui::vec2(point[0] as f32, point[1] as f32) + egui::Vec2::splat(0.5)) / image_size * rect.size()
+        };
+        
+        // pinned points are numbered so they can be found in the panel
+        let painter = ui.painter_at(rect);
+        for (i, point) in self.pins.iter().enumerate() {
+            let center = to_screen(*point);
+            painter.circle_stroke(center, PIN_RADIUS, egui::Stroke::new(2.0, egui::Color32::BLACK));
+            painter.circle_stroke(center, PIN_RADIUS, egui::Stroke::new(1.0, egui::Color32::WHITE));
+            painter.text(
+                center + egui::vec2(PIN_RADIUS, -PIN_RADIUS),
+                egui::Align2::LEFT_BOTTOM,
+                format!("{}", i + 1),
+                egui::FontId::proportional(14.0),
+                egui::Color32::WHITE,
+            );
+        }
+        
+        let hovered = response.hover_pos().map(to_image);
+        if let Some(point) = response.interact_pointer_pos().filter(|_| response.clicked()).map(to_image) {
+            self.pins.push(point);
+        }
+        
+        if let Some(point) = hovered {
+            response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!("x: {}, y: {}", point[0], point[1]));
+                if let Some(sample) = Self::sample(pipeline, point) {
+                    sample_ui(ui, "hover_sample", &sample);
+                }
+                ui.weak("Click to pin the point");
+            });
+        }
+    }
+    
+    /// Panel with values of pinned points
+    pub fn panel_ui(&mut self, ui: &mut egui::Ui, pipeline: &ModifierPipeline) {
+        egui::CollapsingHeader::new(texts::sized("Pixel inspector", 17.0))
+            .default_open(false)
+            .show(ui, |ui| {
+                if self.pins.is_empty() {
+                    ui.label("Click into the image to pin a sample point");
+                    return;
+                }
+                
+                let mut removed = None;
+                for (i, point) in self.pins.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. x: {}, y: {}", i + 1, point[0], point[1]));
+                        if ui.small_button("✖").on_hover_text("Remove point").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                    if let Some(sample) = Self::sample(pipeline, *point) {
+                        sample_ui(ui, ("pin_sample", i), &sample);
+                    }
+                    ui.separator();
+                }
+                
+                if let Some(i) = removed {
+                    self.pins.remove(i);
+                }
+            });
+    }
+}
+
+/// Table of original and edited values of a sample in RGB, HSL and YUV
+fn sample_ui(ui: &mut egui::Ui, id_source: impl std::hash::Hash, sample: &PixelSample) {
+    let rgb = |c: [f32; 3]| format!("{:.0}, {:.0}, {:.0}", c[0] * 255.0, c[1] * 255.0, c[2] * 255.0);
+    let hsl = |c: [f32; 3]| {
+        let [h, s, l] = rgb_to_hsl(c);
+        format!("{:.0}°, {:.0}%, {:.0}%", h, s * 100.0, l * 100.0)
+    };
+    let yuv = |c: [f32; 3]| {
+        let yuv = rgb_to_yuv_matrix() * Vector3::new(c[0], c[1], c[2]);
+        format!("{:.3}, {:.3}, {:.3}", yuv.x, yuv.y, yuv.z)
+    };
+    
+    egui::Grid::new(id_source)
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label("Original");
+            ui.label("Edited");
+            ui.end_row();
+            
+            let rows = [
+                ("RGB", rgb(sample.original), rgb(sample.edited)),
+                ("HSL", hsl(sample.original), hsl(sample.edited)),
+                ("YUV", yuv(sample.original), yuv(sample.edited)),
+            ];
+            for (label, original, edited) in rows {
+                ui.label(label);
+                ui.monospace(original);
+                ui.monospace(edited);
+                ui.end_row();
+            }
+        });
+}
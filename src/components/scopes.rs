@@ -0,0 +1,318 @@
+use cgmath::Vector3;
+use egui::{Color32, Stroke};
+
+use crate::widgets::texts;
+
+use super::{
+    Image,
+    ModifierPipeline,
+    modifiers::specific::{rgb_to_yuv_matrix, yuv_to_rgb_matrix},
+};
+
+/// Number of levels on vertical axis of the waveform and size of the vectorscope in pixels
+const SCOPE_RESOLUTION: usize = 256;
+
+/// Number of columns the image width is binned into for every waveform channel
+const WAVEFORM_COLUMNS: usize = 256;
+
+/// Largest absolute value of U and V components shown in the vectorscope
+const VECTORSCOPE_RANGE: f32 = 0.65;
+
+/// Angle of the skin-tone line in the vectorscope in degrees measured from U axis counterclockwise
+const SKIN_TONE_ANGLE: f32 = 123.0;
+
+/// Brightness gain of scope traces, higher values make sparse traces more visible
+const TRACE_GAIN: f32 = 60.0;
+
+/// What is plotted in the waveform
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WaveformMode {
+    /// luma of the image
+    Luma,
+    /// red, green and blue channels side by side
+    Parade,
+}
+
+/// Where the scopes are rendered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScopesDock {
+    Left,
+    Bottom,
+    Floating,
+}
+
+impl ScopesDock {
+    pub const ALL: [ScopesDock; 3] = [ScopesDock::Left, ScopesDock::Bottom, ScopesDock::Floating];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScopesDock::Left => "Left",
+            ScopesDock::Bottom => "Bottom",
+            ScopesDock::Floating => "Floating",
+        }
+    }
+}
+
+/// Waveform and vectorscope of the current image of a pipeline
+pub struct Scopes {
+    pub open: bool,
+    dock: ScopesDock,
+    mode: WaveformMode,
+    
+    /// pipeline revision and mode the textures were computed for
+    computed: Option<(u64, WaveformMode)>,
+    waveform: Option<egui::TextureHandle>,
+    vectorscope: Option<egui::TextureHandle>,
+}
+
+impl Default for Scopes {
+    fn default() -> Self {
+        Self {
+            open: false,
+            dock: ScopesDock::Left,
+            mode: WaveformMode::Luma,
+            computed: None,
+            waveform: None,
+            vectorscope: None,
+        }
+    }
+}
+
+// mutating methods
+impl Scopes {
+    
+    /// Recomputes scopes when the pipeline was evaluated since the last update
+    fn update(&mut self, ctx: &egui::Context, pipeline: &ModifierPipeline) {
+        let key = (pipeline.revision(), self.mode);
+        if self.computed == Some(key) {
+            return;
+        }
+        
+        let image = pipeline.current_image();
+        self.waveform = Some(ctx.load_texture("waveform", waveform(image, self.mode)));
+        self.vectorscope = Some(ctx.load_texture("vectorscope", vectorscope(image)));
+        self.computed = Some(key);
+    }
+}
+
+// ui code
+impl Scopes {
+    
+    /// Renders scopes docked according to settings, has to be called before central panel is shown
+    pub fn ui(&mut self, ctx: &egui::Context, pipeline: &ModifierPipeline) {
+        if !self.open {
+            return;
+        }
+        self.update(ctx, pipeline);
+        
+        match self.dock {
+            ScopesDock::Left => {
+                egui::SidePanel::left("scopes_panel")
+                    .default_width(SCOPE_RESOLUTION as f32)
+                    .show(ctx, |ui| self.scopes_ui(ui, true));
+            },
+            ScopesDock::Bottom => {
+                egui::TopBottomPanel::bottom("scopes_panel")
+                    .resizable(true)
+                    .default_height(SCOPE_RESOLUTION as f32)
+                    .show(ctx, |ui| self.scopes_ui(ui, false));
+            },
+            ScopesDock::Floating => {
+                let mut open = self.open;
+                egui::Window::new("Scopes")
+                    .open(&mut open)
+                    .default_width(SCOPE_RESOLUTION as f32)
+                    .show(ctx, |ui| self.scopes_ui(ui, true));
+                self.open = open;
+            },
+        }
+    }
+    
+    /// Button toggling visibility of the scopes
+    pub fn toggle_ui(&mut self, ui: &mut egui::Ui) {
+        if ui.selectable_label(self.open, "📊 Scopes").clicked() {
+            self.open = !self.open;
+        }
+    }
+    
+    fn scopes_ui(&mut self, ui: &mut egui::Ui, vertical: bool) {
+        ui.horizontal(|ui| {
+            ui.label(texts::sized("Scopes", 17.0));
+            egui::ComboBox::from_id_source("scopes_dock")
+                .selected_text(self.dock.name())
+                .show_ui(ui, |ui| {
+                    for dock in ScopesDock::ALL {
+                        ui.selectable_value(&mut self.dock, dock, dock.name());
+                    }
+                });
+            ui.selectable_value(&mut self.mode, WaveformMode::Luma, "Luma");
+            ui.selectable_value(&mut self.mode, WaveformMode::Parade, "RGB parade");
+        });
+        
+        let (waveform, vectorscope) = match (&self.waveform, &self.vectorscope) {
+            (Some(w), Some(v)) => (w, v),
+            _ => return,
+        };
+        
+        // scopes are stacked in side panel and placed next to each other in bottom panel
+        let available = ui.available_size();
+        let side = match vertical {
+            true => available.x.min(available.y * 0.5),
+            false => available.y.min(available.x * 0.5),
+        }.max(64.0);
+        let waveform_size = egui::vec2(side * waveform.aspect_ratio(), side);
+        
+        let show = |ui: &mut egui::Ui| {
+            waveform_ui(ui, waveform, waveform_size);
+            vectorscope_ui(ui, vectorscope, egui::Vec2::splat(side));
+        };
+        match vertical {
+            true => { ui.vertical(show); },
+            false => { ui.horizontal(show); },
+        }
+    }
+}
+
+fn waveform_ui(ui: &mut egui::Ui, texture: &egui::TextureHandle, size: egui::Vec2) {
+    let response = ui.image(texture, size);
+    let rect = response.rect;
+    let painter = ui.painter_at(rect);
+    
+    // reference lines at every quarter of the range
+    for i in 0..=4 {
+        let y = rect.bottom() - rect.height() * i as f32 / 4.0;
+        painter.line_segment(
+            [egui::pos2(rect.left(), y), egui::pos2(rect.right(), y)],
+            Stroke::new(1.0, Color32::from_white_alpha(40)),
+        );
+    }
+}
+
+fn vectorscope_ui(ui: &mut egui::Ui, texture: &egui::TextureHandle, size: egui::Vec2) {
+    let response = ui.image(texture, size);
+    let rect = response.rect;
+    let painter = ui.painter_at(rect);
+    let center = rect.center();
+    let radius = rect.width() * 0.5;
+    let to_screen = |u: f32, v: f32| center + egui::vec2(u, -v) / VECTORSCOPE_RANGE * radius;
+    let graticule = Stroke::new(1.0, Color32::from_white_alpha(40));
+    
+    painter.circle_stroke(center, radius, graticule);
+    painter.line_segment([egui::pos2(rect.left(), center.y), egui::pos2(rect.right(), center.y)], graticule);
+    painter.line_segment([egui::pos2(center.x, rect.top()), egui::pos2(center.x, rect.bottom())], graticule);
+    
+    // targets of fully saturated primary and secondary colors
+    let targets = [
+        ("R", [1.0, 0.0, 0.0]),
+        ("Y", [1.0, 1.0, 0.0]),
+        ("G", [0.0, 1.0, 0.0]),
+        ("C", [0.0, 1.0, 1.0]),
+        ("B", [0.0, 0.0, 1.0]),
+        ("M", [1.0, 0.0, 1.0]),
+    ];
+    for (label, rgb) in targets {
+        let yuv = rgb_to_yuv_matrix() * Vector3::from(rgb);
+        let pos = to_screen(yuv.y, yuv.z);
+        painter.rect_stroke(egui::Rect::from_center_size(pos, egui::Vec2::splat(8.0)), 0.0, graticule);
+        painter.text(
+            pos + egui::vec2(6.0, -6.0),
+            egui::Align2::LEFT_BOTTOM,
+            label,
+            egui::FontId::proportional(11.0),
+            Color32::from_white_alpha(120),
+        );
+    }
+    
+    let angle = SKIN_TONE_ANGLE.to_radians();
+    painter.line_segment(
+        [center, center + egui::vec2(angle.cos(), -angle.sin()) * radius],
+        Stroke::new(1.0, Color32::from_rgba_unmultiplied(255, 200, 150, 120)),
+    );
+}
+
+/// Accumulates counts into scope image with traces brighter where more pixels fall.
+///   `color` gives color of a trace of every cell of the counts grid.
+fn trace_image(size: [usize; 2], counts: &[u32], samples_per_column: f32, color: impl Fn(usize, usize) -> [f32; 3]) -> egui::ColorImage {
+    let mut image = egui::ColorImage::new(size, Color32::BLACK);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let count = counts[y * size[0] + x];
+            if count == 0 {
+                continue;
+            }
+            let intensity = 1.0 - (-(count as f32) * TRACE_GAIN / samples_per_column.max(1.0)).exp();
+            let c = color(x, y).map(|c| (c * intensity * 255.0).clamp(0.0, 255.0) as u8);
+            image.pixels[y * size[0] + x] = Color32::from_rgb(c[0], c[1], c[2]);
+        }
+    }
+    image
+}
+
+/// Waveform of luma or RGB parade, values in rows from top level to bottom level
+fn waveform(image: &Image, mode: WaveformMode) -> egui::ColorImage {
+    let pixels = image.perceptual_pixels();
+    let channels = match mode {
+        WaveformMode::Luma => 1,
+        WaveformMode::Parade => 3,
+    };
+    let width = WAVEFORM_COLUMNS * channels;
+    let mut counts = vec![0u32; width * SCOPE_RESOLUTION];
+    let luma = rgb_to_yuv_matrix().x;
+    let to_level = |v: f32| SCOPE_RESOLUTION - 1 - (v.clamp(0.0, 1.0) * (SCOPE_RESOLUTION - 1) as f32).round() as usize;
+    
+    let image_width = pixels.width().max(1) as usize;
+    for (x, _, p) in pixels.enumerate_pixels() {
+        if p[3] == 0.0 {
+            continue;
+        }
+        let column = x as usize * WAVEFORM_COLUMNS / image_width;
+        match mode {
+            WaveformMode::Luma => {
+                let y = luma.x * p[0] + luma.y * p[1] + luma.z * p[2];
+                counts[to_level(y) * width + column] += 1;
+            },
+            WaveformMode::Parade => {
+                for channel in 0..3 {
+                    counts[to_level(p[channel]) * width + channel * WAVEFORM_COLUMNS + column] += 1;
+                }
+            },
+        }
+    }
+    
+    let samples_per_column = pixels.height() as f32 * image_width as f32 / WAVEFORM_COLUMNS as f32 / SCOPE_RESOLUTION as f32;
+    trace_image([width, SCOPE_RESOLUTION], &counts, samples_per_column, |x, _| match mode {
+        WaveformMode::Luma => [0.9, 0.95, 0.9],
+        WaveformMode::Parade => {
+            let mut color = [0.25; 3];
+            color[x / WAVEFORM_COLUMNS] = 1.0;
+            color
+        },
+    })
+}
+
+/// Vectorscope plotting U on horizontal and V on vertical axis
+fn vectorscope(image: &Image) -> egui::ColorImage {
+    let pixels = image.perceptual_pixels();
+    let size = SCOPE_RESOLUTION;
+    let mut counts = vec![0u32; size * size];
+    let to_yuv = rgb_to_yuv_matrix();
+    let to_cell = |v: f32| ((v / VECTORSCOPE_RANGE * 0.5 + 0.5).clamp(0.0, 1.0) * (size - 1) as f32).round() as usize;
+    
+    for p in pixels.pixels() {
+        if p[3] == 0.0 {
+            continue;
+        }
+        let yuv = to_yuv * Vector3::new(p[0], p[1], p[2]);
+        counts[(size - 1 - to_cell(yuv.z)) * size + to_cell(yuv.y)] += 1;
+    }
+    
+    // traces are colored by the hue at their position so the scope reads like a color wheel
+    let to_rgb = yuv_to_rgb_matrix();
+    let samples = pixels.width() as f32 * pixels.height() as f32 / size as f32;
+    trace_image([size, size], &counts, samples, |x, y| {
+        let u = (x as f32 / (size - 1) as f32 - 0.5) * 2.0 * VECTORSCOPE_RANGE;
+        let v = (0.5 - y as f32 / (size - 1) as f32) * 2.0 * VECTORSCOPE_RANGE;
+        let rgb = to_rgb * Vector3::new(0.6, u, v);
+        [rgb.x.clamp(0.2, 1.0), rgb.y.clamp(0.2, 1.0), rgb.z.clamp(0.2, 1.0)]
+    })
+}
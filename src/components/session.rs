@@ -0,0 +1,24 @@
+use serde::{Serialize, Deserialize};
+
+use super::Recipe;
+
+/// Maximal number of files remembered in recent files menu
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// State of the application stored between runs
+#[derive(Serialize, Deserialize, Default)]
+pub struct Session {
+    /// most recently opened file is first
+    pub recent_files: Vec<String>,
+    pub documents: Vec<DocumentSession>,
+    pub active_document: usize,
+}
+
+/// State of one opened image
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DocumentSession {
+    pub path: String,
+    pub recipe: Recipe,
+    pub zoom_level: f32,
+    pub offset: egui::Vec2,
+}
@@ -3,8 +3,25 @@ use file_format::FileFormat;
 
 pub const SUPPORTED_MULTIMEDIA_FILE_FORMATS : &'static [FileFormat] = &[
     FileFormat::JointPhotographicExpertsGroup,
+    FileFormat::PortableNetworkGraphics,
+    FileFormat::TagImageFileFormat,
 ];
 
+pub const KEYMAP_FILE_NAME: &str = "keymap.conf";
+pub const MATRIX_PRESETS_FILE_NAME: &str = "matrix_presets.conf";
+
 pub const RIGHT_PANEL_WIDTH: f32 = 370.0;
 pub const THUMBNAIL_SIZE: f32 = 50.0;
+pub const FILMSTRIP_THUMBNAIL_SIZE: f32 = 80.0;
 pub const MIN_SLIDER_WIDTH: f32 = 50.0;
+
+/// Zoom levels are powers of two relative to image fitting into the viewport
+pub const ZOOM_STEP: f32 = 0.5;
+pub const MIN_ZOOM_LEVEL: f32 = -3.0;
+pub const MAX_ZOOM_LEVEL: f32 = 5.0;
+
+/// Edge sizes of 3D LUT offered on export, 33 is the most common size in video tools
+pub const LUT_EXPORT_SIZES: &[usize] = &[17, 33, 65];
+
+/// Maximal number of undo steps kept for each image
+pub const MAX_HISTORY: usize = 100;
@@ -0,0 +1,92 @@
+
+use image::Rgba32FImage;
+use img_parts::{Bytes, DynImage, ImageICC};
+use lcms2::{Profile, Transform, PixelFormat, Intent, CIExyY, CIExyYTRIPLE, ToneCurve};
+
+use super::metadata::is_embeddable;
+
+/// White point of all supported profiles
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+const DISPLAY_P3_PRIMARIES: [(f64, f64); 3] = [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)];
+const ADOBE_RGB_PRIMARIES: [(f64, f64); 3] = [(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)];
+
+/// Color profiles the edited image can be exported in.
+///   The editor itself always works with sRGB colors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorProfile {
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+}
+
+impl ColorProfile {
+    
+    pub const ALL: [ColorProfile; 3] = [ColorProfile::Srgb, ColorProfile::DisplayP3, ColorProfile::AdobeRgb];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorProfile::Srgb => "sRGB",
+            ColorProfile::DisplayP3 => "Display P3",
+            ColorProfile::AdobeRgb => "Adobe RGB",
+        }
+    }
+    
+    pub fn profile(&self) -> Profile {
+        match self {
+            ColorProfile::Srgb => Profile::new_srgb(),
+            // Display P3 shares transfer curve with sRGB
+            ColorProfile::DisplayP3 => rgb_profile(
+                DISPLAY_P3_PRIMARIES,
+                ToneCurve::new_parametric(4, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045])
+                    .expect("valid sRGB curve parameters")
+            ),
+            ColorProfile::AdobeRgb => rgb_profile(ADOBE_RGB_PRIMARIES, ToneCurve::new(563.0 / 256.0)),
+        }
+    }
+    
+    /// Serialized profile to be embedded into image files
+    pub fn icc(&self) -> Option<Vec<u8>> {
+        self.profile().icc().ok()
+    }
+}
+
+fn rgb_profile(primaries: [(f64, f64); 3], curve: ToneCurve) -> Profile {
+    let xy = |(x, y): (f64, f64)| CIExyY { x, y, Y: 1.0 };
+    let primaries = CIExyYTRIPLE {
+        Red: xy(primaries[0]),
+        Green: xy(primaries[1]),
+        Blue: xy(primaries[2]),
+    };
+    Profile::new_rgb(&xy(D65), &primaries, &[&curve, &curve, &curve]).expect("valid profile parameters")
+}
+
+/// Converts colors of every pixel from one profile into another leaving alpha untouched
+pub fn transform_pixels(image: &mut Rgba32FImage, from: &Profile, to: &Profile) -> Result<(), String> {
+    let transform = Transform::<[f32; 4], [f32; 4]>::new(
+        from, PixelFormat::RGBA_FLT,
+        to, PixelFormat::RGBA_FLT,
+        Intent::Perceptual
+    ).map_err(|err| err.to_string())?;
+    
+    let mut pixels: Vec<[f32; 4]> = image.pixels().map(|p| p.0).collect();
+    transform.transform_in_place(&mut pixels);
+    image.pixels_mut().zip(pixels).for_each(|(p, transformed)| p.0 = transformed);
+    Ok(())
+}
+
+/// Writes ICC profile into encoded image file.
+///   Only JPEG and PNG containers are supported, other files are returned unchanged.
+pub fn embed_profile(encoded: Vec<u8>, icc: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_embeddable(&encoded) {
+        return Ok(encoded);
+    }
+    match DynImage::from_bytes(Bytes::from(encoded)) {
+        Ok(Some(mut image)) => {
+            image.set_icc_profile(Some(Bytes::copy_from_slice(icc)));
+            Ok(image.encoder().bytes().to_vec())
+        },
+        Ok(None) => Err("Unknown format of encoded image".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
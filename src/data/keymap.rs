@@ -0,0 +1,438 @@
+use std::{fmt::{self, Display}, path::{Path, PathBuf}};
+
+use egui::{Key, Modifiers};
+
+/// Actions which can be triggered by keyboard shortcuts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Open,
+    Save,
+    Export,
+    Undo,
+    Redo,
+    ZoomIn,
+    ZoomOut,
+    ZoomFit,
+    /// shows original image while the shortcut is held down
+    ShowOriginal,
+    ToggleCompare,
+    NextImage,
+    PreviousImage,
+    ResetAll,
+    CheatSheet,
+}
+
+impl Action {
+    
+    pub const ALL: [Action; 14] = [
+        Action::Open,
+        Action::Save,
+        Action::Export,
+        Action::Undo,
+        Action::Redo,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomFit,
+        Action::ShowOriginal,
+        Action::ToggleCompare,
+        Action::NextImage,
+        Action::PreviousImage,
+        Action::ResetAll,
+        Action::CheatSheet,
+    ];
+    
+    /// Name of the action in the config file
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::Open => "open",
+            Action::Save => "save",
+            Action::Export => "export",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ZoomFit => "zoom_fit",
+            Action::ShowOriginal => "show_original",
+            Action::ToggleCompare => "toggle_compare",
+            Action::NextImage => "next_image",
+            Action::PreviousImage => "previous_image",
+            Action::ResetAll => "reset_all",
+            Action::CheatSheet => "cheat_sheet",
+        }
+    }
+    
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Open => "Open file",
+            Action::Save => "Save",
+            Action::Export => "Export as",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ZoomFit => "Zoom to fit",
+            Action::ShowOriginal => "Show original (hold)",
+            Action::ToggleCompare => "Toggle comparison with original",
+            Action::NextImage => "Next image in folder",
+            Action::PreviousImage => "Previous image in folder",
+            Action::ResetAll => "Reset all modifiers",
+            Action::CheatSheet => "Show keyboard shortcuts",
+        }
+    }
+    
+    fn default_shortcut(&self) -> &'static str {
+        match self {
+            Action::Open => "Ctrl+O",
+            Action::Save => "Ctrl+S",
+            Action::Export => "Ctrl+Shift+S",
+            Action::Undo => "Ctrl+Z",
+            Action::Redo => "Ctrl+Y",
+            Action::ZoomIn => "Ctrl+ArrowUp",
+            Action::ZoomOut => "Ctrl+ArrowDown",
+            Action::ZoomFit => "Ctrl+Num0",
+            Action::ShowOriginal => "Space",
+            Action::ToggleCompare => "C",
+            Action::NextImage => "ArrowRight",
+            Action::PreviousImage => "ArrowLeft",
+            Action::ResetAll => "Ctrl+R",
+            Action::CheatSheet => "Ctrl+H",
+        }
+    }
+}
+
+/// Key with modifiers, `Ctrl` stands for command key on Mac
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Shortcut {
+    pub key: Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Shortcut {
+    
+    /// Parses shortcut in format like `Ctrl+Shift+S`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(|p| p.trim()).collect();
+        let key_name = parts.pop().unwrap_or_default();
+        let key = KEYS
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(key_name))
+            .map(|(key, _)| *key)
+            .ok_or_else(|| format!("unknown key \"{key_name}\""))?;
+        
+        let mut shortcut = Shortcut { key, ctrl: false, shift: false, alt: false };
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" => shortcut.ctrl = true,
+                "shift" => shortcut.shift = true,
+                "alt" => shortcut.alt = true,
+                _ => return Err(format!("unknown modifier \"{modifier}\"")),
+            }
+        }
+        Ok(shortcut)
+    }
+    
+    fn matches(&self, modifiers: &Modifiers) -> bool {
+        self.ctrl == modifiers.command && self.shift == modifiers.shift && self.alt == modifiers.alt
+    }
+    
+    fn has_modifiers(&self) -> bool {
+        self.ctrl || self.shift || self.alt
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        let name = KEYS.iter().find(|(key, _)| *key == self.key).map_or("?", |(_, name)| *name);
+        write!(f, "{name}")
+    }
+}
+
+/// Assignment of keyboard shortcuts to actions
+pub struct Keymap {
+    bindings: Vec<(Action, Shortcut)>,
+    
+    /// config file the keymap was loaded from
+    path: Option<PathBuf>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|a| (*a, Shortcut::parse(a.default_shortcut()).expect("valid default shortcut")))
+                .collect(),
+            path: None,
+        }
+    }
+}
+
+// constructors
+impl Keymap {
+    
+    /// Loads keymap from config file with lines like `save = Ctrl+S`, actions missing in the file keep default shortcuts.
+    ///   Default config is written when the file does not exist.
+    ///   Returns the keymap together with descriptions of invalid or conflicting lines.
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        let (mut keymap, problems) = match std::fs::read_to_string(path) {
+            Ok(config) => Self::parse(&config),
+            Err(_) => {
+                let keymap = Self::default();
+                if let Some(dir) = path.parent() {
+                    let _ = std::fs::create_dir_all(dir);
+                }
+                let _ = std::fs::write(path, keymap.to_config());
+                (keymap, vec![])
+            },
+        };
+        keymap.path = Some(path.to_path_buf());
+        (keymap, problems)
+    }
+    
+    pub fn parse(config: &str) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut problems = vec![];
+        let mut configured = vec![];
+        
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => {
+                    problems.push(format!("Keymap line {}: expected \"action = shortcut\"", number + 1));
+                    continue;
+                },
+            };
+            
+            let action = match Action::ALL.iter().find(|a| a.key() == name) {
+                Some(action) => *action,
+                None => {
+                    problems.push(format!("Keymap line {}: unknown action \"{name}\"", number + 1));
+                    continue;
+                },
+            };
+            
+            // empty value removes the shortcut
+            keymap.bindings.retain(|(a, _)| *a != action);
+            configured.push(action);
+            if value.is_empty() {
+                continue;
+            }
+            match Shortcut::parse(value) {
+                Ok(shortcut) => keymap.bindings.push((action, shortcut)),
+                Err(message) => problems.push(format!("Keymap line {}: {message}", number + 1)),
+            }
+        }
+        
+        problems.extend(keymap.remove_conflicts(&configured));
+        (keymap, problems)
+    }
+    
+    /// Removes shortcuts assigned to more than one action. Shortcuts of `configured` actions take precedence
+    ///   over defaults of the other actions, among configured actions the first assignment wins.
+    fn remove_conflicts(&mut self, configured: &[Action]) -> Vec<String> {
+        let mut problems = vec![];
+        let mut kept: Vec<(Action, Shortcut)> = vec![];
+        self.bindings.sort_by_key(|(a, _)| !configured.contains(a));
+        for (action, shortcut) in self.bindings.drain(..) {
+            match kept.iter().find(|(_, s)| *s == shortcut) {
+                Some((other, _)) if configured.contains(&action) => problems.push(format!(
+                    "Shortcut {shortcut} is assigned to both \"{}\" and \"{}\", it is used only for the first one",
+                    other.description(),
+                    action.description()
+                )),
+                Some((other, _)) => problems.push(format!(
+                    "Default shortcut {shortcut} of \"{}\" is overridden by \"{}\", the action has no shortcut",
+                    action.description(),
+                    other.description()
+                )),
+                None => kept.push((action, shortcut)),
+            }
+        }
+        self.bindings = kept;
+        problems
+    }
+}
+
+// properties
+impl Keymap {
+    
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+    
+    pub fn shortcut(&self, action: Action) -> Option<Shortcut> {
+        self.bindings.iter().find(|(a, _)| *a == action).map(|(_, s)| *s)
+    }
+    
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# action = shortcut, e.g. save = Ctrl+S, empty shortcut disables the action\n");
+        for action in Action::ALL {
+            let shortcut = self.shortcut(action).map(|s| s.to_string()).unwrap_or_default();
+            config.push_str(&format!("{} = {}\n", action.key(), shortcut));
+        }
+        config
+    }
+    
+    /// Actions whose shortcuts were pressed in this frame.
+    ///   No shortcut is triggered while text is edited, shortcuts without modifiers are ignored while some widget has keyboard focus.
+    pub fn triggered(&self, ctx: &egui::Context) -> Vec<Action> {
+        if ctx.wants_keyboard_input() {
+            return vec![];
+        }
+        let focused = ctx.memory().focus().is_some();
+        let input = ctx.input();
+        self.bindings
+            .iter()
+            .filter(|(_, s)| !focused || s.has_modifiers())
+            .filter(|(_, s)| input.key_pressed(s.key) && s.matches(&input.modifiers))
+            .map(|(a, _)| *a)
+            .collect()
+    }
+    
+    /// Whether the shortcut of the action is held down, keys pressed while text is edited do not count
+    pub fn is_down(&self, ctx: &egui::Context, action: Action) -> bool {
+        if ctx.wants_keyboard_input() {
+            return false;
+        }
+        let input = ctx.input();
+        self.shortcut(action).is_some_and(|s| input.key_down(s.key) && s.matches(&input.modifiers))
+    }
+}
+
+/// Names of keys used in config file
+const KEYS: &[(Key, &str)] = &[
+    (Key::ArrowDown, "ArrowDown"),
+    (Key::ArrowLeft, "ArrowLeft"),
+    (Key::ArrowRight, "ArrowRight"),
+    (Key::ArrowUp, "ArrowUp"),
+    (Key::Escape, "Escape"),
+    (Key::Tab, "Tab"),
+    (Key::Backspace, "Backspace"),
+    (Key::Enter, "Enter"),
+    (Key::Space, "Space"),
+    (Key::Insert, "Insert"),
+    (Key::Delete, "Delete"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::Num0, "Num0"),
+    (Key::Num1, "Num1"),
+    (Key::Num2, "Num2"),
+    (Key::Num3, "Num3"),
+    (Key::Num4, "Num4"),
+    (Key::Num5, "Num5"),
+    (Key::Num6, "Num6"),
+    (Key::Num7, "Num7"),
+    (Key::Num8, "Num8"),
+    (Key::Num9, "Num9"),
+    (Key::A, "A"),
+    (Key::B, "B"),
+    (Key::C, "C"),
+    (Key::D, "D"),
+    (Key::E, "E"),
+    (Key::F, "F"),
+    (Key::G, "G"),
+    (Key::H, "H"),
+    (Key::I, "I"),
+    (Key::J, "J"),
+    (Key::K, "K"),
+    (Key::L, "L"),
+    (Key::M, "M"),
+    (Key::N, "N"),
+    (Key::O, "O"),
+    (Key::P, "P"),
+    (Key::Q, "Q"),
+    (Key::R, "R"),
+    (Key::S, "S"),
+    (Key::T, "T"),
+    (Key::U, "U"),
+    (Key::V, "V"),
+    (Key::W, "W"),
+    (Key::X, "X"),
+    (Key::Y, "Y"),
+    (Key::Z, "Z"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn shortcut(text: &str) -> Option<Shortcut> {
+        Some(Shortcut::parse(text).unwrap())
+    }
+    
+    #[test]
+    fn shortcut_round_trip() {
+        for text in ["Ctrl+Shift+S", "Alt+ArrowLeft", "Space", "Ctrl+Num0"] {
+            assert_eq!(Shortcut::parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(Shortcut::parse("cmd + shift + s"), Shortcut::parse("Ctrl+Shift+S"));
+    }
+    
+    #[test]
+    fn default_config_round_trip() {
+        let (keymap, problems) = Keymap::parse(&Keymap::default().to_config());
+        assert!(problems.is_empty(), "{problems:?}");
+        for action in Action::ALL {
+            assert_eq!(keymap.shortcut(action), Keymap::default().shortcut(action));
+        }
+    }
+    
+    #[test]
+    fn malformed_lines_are_reported() {
+        let config = "# comment\nsave Ctrl+S\nfly = Ctrl+F\nopen = Ctrl+Foo\nundo = Hyper+Z\n\nredo = Ctrl+Shift+Z\n";
+        let (keymap, problems) = Keymap::parse(config);
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(problems[0].starts_with("Keymap line 2: expected"));
+        assert!(problems[1].contains("unknown action \"fly\""));
+        assert!(problems[2].contains("unknown key \"Foo\""));
+        assert!(problems[3].contains("unknown modifier \"Hyper\""));
+        
+        // invalid shortcut leaves the action without one, other actions keep defaults
+        assert_eq!(keymap.shortcut(Action::Open), None);
+        assert_eq!(keymap.shortcut(Action::Save), shortcut("Ctrl+S"));
+        assert_eq!(keymap.shortcut(Action::Redo), shortcut("Ctrl+Shift+Z"));
+    }
+    
+    #[test]
+    fn empty_value_removes_shortcut() {
+        let (keymap, problems) = Keymap::parse("toggle_compare =\n");
+        assert!(problems.is_empty());
+        assert_eq!(keymap.shortcut(Action::ToggleCompare), None);
+    }
+    
+    #[test]
+    fn configured_shortcut_overrides_default() {
+        let (keymap, problems) = Keymap::parse("undo = Ctrl+S\n");
+        assert_eq!(keymap.shortcut(Action::Undo), shortcut("Ctrl+S"));
+        assert_eq!(keymap.shortcut(Action::Save), None);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("overridden"), "{problems:?}");
+    }
+    
+    #[test]
+    fn first_configured_shortcut_wins() {
+        let (keymap, problems) = Keymap::parse("undo = Ctrl+U\nredo = Ctrl+U\n");
+        assert_eq!(keymap.shortcut(Action::Undo), shortcut("Ctrl+U"));
+        assert_eq!(keymap.shortcut(Action::Redo), None);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("used only for the first one"), "{problems:?}");
+    }
+}
@@ -0,0 +1,389 @@
+use std::path::Path;
+
+/// Largest supported edge of 1D LUT, same limit as the Adobe specification
+const MAX_1D_SIZE: usize = 65536;
+/// Largest supported edge of 3D LUT, same limit as the Adobe specification
+const MAX_3D_SIZE: usize = 256;
+
+/// Method of computing colors between lattice points of 3D LUT
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LutInterpolation {
+    Trilinear,
+    Tetrahedral,
+}
+
+impl LutInterpolation {
+    pub const ALL: [LutInterpolation; 2] = [LutInterpolation::Trilinear, LutInterpolation::Tetrahedral];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            LutInterpolation::Trilinear => "Trilinear",
+            LutInterpolation::Tetrahedral => "Tetrahedral",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LutDimension {
+    /// every channel is mapped by its own curve
+    OneD,
+    /// colors are mapped through a cube lattice
+    ThreeD,
+}
+
+/// Color lookup table in Adobe / Resolve `.cube` format
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lut {
+    pub title: Option<String>,
+    pub dimension: LutDimension,
+    
+    /// number of entries along one edge
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    
+    /// output colors, for 3D LUT red index changes fastest then green then blue
+    pub table: Vec<[f32; 3]>,
+}
+
+// constructors
+impl Lut {
+    
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read LUT file {}: {}", path.display(), e))?;
+        Self::parse(&text)
+            .map_err(|e| format!("Invalid LUT file {}: {}", path.display(), e))
+    }
+    
+    /// Parses content of `.cube` file, errors point to the line with the problem
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut title = None;
+        let mut size_1d = None;
+        let mut size_3d = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = vec![];
+        
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            let rest: Vec<&str> = parts.collect();
+            let error = |message: &str| format!("line {}: {}", line_number, message);
+            
+            // keywords are not allowed once table data started
+            let is_data = keyword.parse::<f32>().is_ok();
+            if !is_data && !table.is_empty() {
+                return Err(error(&format!("unexpected \"{}\" after table data", keyword)));
+            }
+            
+            match keyword {
+                "TITLE" => {
+                    let value = line["TITLE".len()..].trim();
+                    title = Some(value.trim_matches('"').to_string());
+                },
+                "LUT_1D_SIZE" => size_1d = Some(parse_size(&rest, MAX_1D_SIZE).map_err(|e| error(&e))?),
+                "LUT_3D_SIZE" => size_3d = Some(parse_size(&rest, MAX_3D_SIZE).map_err(|e| error(&e))?),
+                "DOMAIN_MIN" => domain_min = parse_triplet(&rest).map_err(|e| error(&e))?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(&rest).map_err(|e| error(&e))?,
+                
+                // Resolve writes input range as two values shared by all channels
+                "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                    let range = parse_values(&rest, 2).map_err(|e| error(&e))?;
+                    domain_min = [range[0]; 3];
+                    domain_max = [range[1]; 3];
+                },
+                _ if is_data => {
+                    let values = parse_values(&[&[keyword], &rest[..]].concat(), 3).map_err(|e| error(&e))?;
+                    table.push([values[0], values[1], values[2]]);
+                },
+                _ => return Err(error(&format!("unknown keyword \"{}\"", keyword))),
+            }
+        }
+        
+        let (dimension, size, expected) = match (size_1d, size_3d) {
+            (Some(_), Some(_)) => return Err("both LUT_1D_SIZE and LUT_3D_SIZE are defined".to_string()),
+            (Some(size), None) => (LutDimension::OneD, size, size),
+            (None, Some(size)) => (LutDimension::ThreeD, size, size * size * size),
+            (None, None) => return Err("missing LUT_1D_SIZE or LUT_3D_SIZE".to_string()),
+        };
+        if table.len() != expected {
+            return Err(format!("expected {} table entries for size {} but found {}", expected, size, table.len()));
+        }
+        if (0..3).any(|i| domain_min[i] >= domain_max[i]) {
+            return Err("DOMAIN_MIN has to be lower than DOMAIN_MAX in every channel".to_string());
+        }
+        
+        Ok(Self { title, dimension, size, domain_min, domain_max, table })
+    }
+}
+
+// properties
+impl Lut {
+    
+    /// Maps color through the table, 1D tables are always interpolated linearly
+    pub fn sample(&self, rgb: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        
+        // position in table coordinates
+        let max = (self.size - 1) as f32;
+        let mut pos = [0.0; 3];
+        for i in 0..3 {
+            let t = (rgb[i] - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i]);
+            pos[i] = t.clamp(0.0, 1.0) * max;
+        }
+        
+        match (self.dimension, interpolation) {
+            (LutDimension::OneD, _) => self.sample_1d(pos),
+            (LutDimension::ThreeD, LutInterpolation::Trilinear) => self.sample_trilinear(pos),
+            (LutDimension::ThreeD, LutInterpolation::Tetrahedral) => self.sample_tetrahedral(pos),
+        }
+    }
+    
+    fn sample_1d(&self, pos: [f32; 3]) -> [f32; 3] {
+        let mut out = [0.0; 3];
+        for i in 0..3 {
+            let (lo, hi, f) = split(pos[i], self.size);
+            out[i] = self.table[lo][i] + (self.table[hi][i] - self.table[lo][i]) * f;
+        }
+        out
+    }
+    
+    #[inline]
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + self.size * (g + self.size * b)]
+    }
+    
+    fn sample_trilinear(&self, pos: [f32; 3]) -> [f32; 3] {
+        let (r0, r1, fr) = split(pos[0], self.size);
+        let (g0, g1, fg) = split(pos[1], self.size);
+        let (b0, b1, fb) = split(pos[2], self.size);
+        
+        let lerp = |a: [f32; 3], b: [f32; 3], f: f32| [
+            a[0] + (b[0] - a[0]) * f,
+            a[1] + (b[1] - a[1]) * f,
+            a[2] + (b[2] - a[2]) * f,
+        ];
+        
+        let c00 = lerp(self.at(r0, g0, b0), self.at(r1, g0, b0), fr);
+        let c10 = lerp(self.at(r0, g1, b0), self.at(r1, g1, b0), fr);
+        let c01 = lerp(self.at(r0, g0, b1), self.at(r1, g0, b1), fr);
+        let c11 = lerp(self.at(r0, g1, b1), self.at(r1, g1, b1), fr);
+        
+        lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb)
+    }
+    
+    /// Splits the lattice cell into six tetrahedra sharing the black-white diagonal and
+    ///   interpolates between four corners of the one containing the color
+    fn sample_tetrahedral(&self, pos: [f32; 3]) -> [f32; 3] {
+        let (r0, r1, fr) = split(pos[0], self.size);
+        let (g0, g1, fg) = split(pos[1], self.size);
+        let (b0, b1, fb) = split(pos[2], self.size);
+        
+        let c000 = self.at(r0, g0, b0);
+        let c111 = self.at(r1, g1, b1);
+        
+        // corners and weights along the path from black to white corner of the cell
+        let (c1, c2, w) = if fr > fg {
+            if fg > fb {
+                (self.at(r1, g0, b0), self.at(r1, g1, b0), [1.0 - fr, fr - fg, fg - fb, fb])
+            } else if fr > fb {
+                (self.at(r1, g0, b0), self.at(r1, g0, b1), [1.0 - fr, fr - fb, fb - fg, fg])
+            } else {
+                (self.at(r0, g0, b1), self.at(r1, g0, b1), [1.0 - fb, fb - fr, fr - fg, fg])
+            }
+        } else if fb > fg {
+            (self.at(r0, g0, b1), self.at(r0, g1, b1), [1.0 - fb, fb - fg, fg - fr, fr])
+        } else if fb > fr {
+            (self.at(r0, g1, b0), self.at(r0, g1, b1), [1.0 - fg, fg - fb, fb - fr, fr])
+        } else {
+            (self.at(r0, g1, b0), self.at(r1, g1, b0), [1.0 - fg, fg - fr, fr - fb, fb])
+        };
+        
+        let mut out = [0.0; 3];
+        for i in 0..3 {
+            out[i] = w[0] * c000[i] + w[1] * c1[i] + w[2] * c2[i] + w[3] * c111[i];
+        }
+        out
+    }
+}
+
+// conversion
+impl Lut {
+    
+    /// Identity 3D lattice colors in table order, red index changes fastest
+    pub fn identity_lattice(size: usize) -> Vec<[f32; 3]> {
+        let max = (size - 1) as f32;
+        let mut lattice = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    lattice.push([r as f32 / max, g as f32 / max, b as f32 / max]);
+                }
+            }
+        }
+        lattice
+    }
+    
+    /// Writes the table in `.cube` format
+    pub fn to_cube(&self) -> String {
+        let mut cube = String::new();
+        if let Some(title) = &self.title {
+            cube += &format!("TITLE \"{}\"\n", title.replace('"', "'"));
+        }
+        cube += &match self.dimension {
+            LutDimension::OneD => format!("LUT_1D_SIZE {}\n", self.size),
+            LutDimension::ThreeD => format!("LUT_3D_SIZE {}\n", self.size),
+        };
+        let [r, g, b] = self.domain_min;
+        cube += &format!("DOMAIN_MIN {} {} {}\n", r, g, b);
+        let [r, g, b] = self.domain_max;
+        cube += &format!("DOMAIN_MAX {} {} {}\n", r, g, b);
+        for [r, g, b] in &self.table {
+            cube += &format!("{:.6} {:.6} {:.6}\n", r, g, b);
+        }
+        cube
+    }
+    
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_cube())
+            .map_err(|e| format!("Cannot write LUT file {}: {}", path.display(), e))
+    }
+}
+
+/// Splits position in table into indices of surrounding entries and fraction between them
+#[inline]
+fn split(pos: f32, size: usize) -> (usize, usize, f32) {
+    let lo = (pos.floor() as usize).min(size - 1);
+    let hi = (lo + 1).min(size - 1);
+    (lo, hi, pos - lo as f32)
+}
+
+fn parse_values(parts: &[&str], count: usize) -> Result<Vec<f32>, String> {
+    if parts.len() != count {
+        return Err(format!("expected {} values but found {}", count, parts.len()));
+    }
+    parts
+        .iter()
+        .map(|p| p.parse::<f32>().map_err(|_| format!("\"{}\" is not a number", p)))
+        .collect()
+}
+
+fn parse_triplet(parts: &[&str]) -> Result<[f32; 3], String> {
+    let values = parse_values(parts, 3)?;
+    Ok([values[0], values[1], values[2]])
+}
+
+fn parse_size(parts: &[&str], max: usize) -> Result<usize, String> {
+    let size = match parts {
+        [value] => value.parse::<usize>().map_err(|_| format!("\"{}\" is not a valid size", value))?,
+        _ => return Err("size has to be a single number".to_string()),
+    };
+    if !(2..=max).contains(&size) {
+        return Err(format!("size {} is out of supported range 2 - {}", size, max));
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn lut_3d(size: usize, color: impl Fn([f32; 3]) -> [f32; 3]) -> Lut {
+        Lut {
+            title: Some("Test".to_string()),
+            dimension: LutDimension::ThreeD,
+            size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            table: Lut::identity_lattice(size).into_iter().map(color).collect(),
+        }
+    }
+    
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-5), "{a:?} != {b:?}");
+    }
+    
+    fn parse_error(cube: &str) -> String {
+        Lut::parse(cube).expect_err("cube should be refused")
+    }
+    
+    #[test]
+    fn identity_round_trip() {
+        let lut = lut_3d(3, |c| c);
+        assert_eq!(Lut::parse(&lut.to_cube()), Ok(lut));
+    }
+    
+    #[test]
+    fn one_d_round_trip_with_domain() {
+        let lut = Lut {
+            title: None,
+            dimension: LutDimension::OneD,
+            size: 2,
+            domain_min: [-0.5; 3],
+            domain_max: [2.0; 3],
+            table: vec![[0.0; 3], [1.0, 0.5, 0.25]],
+        };
+        assert_eq!(Lut::parse(&lut.to_cube()), Ok(lut));
+    }
+    
+    #[test]
+    fn wrong_entry_count() {
+        let error = parse_error("LUT_3D_SIZE 2\n0 0 0\n1 1 1\n");
+        assert!(error.contains("expected 8 table entries"), "{error}");
+    }
+    
+    #[test]
+    fn invalid_sizes() {
+        assert!(parse_error("LUT_3D_SIZE 1\n").contains("out of supported range"));
+        assert!(parse_error("LUT_3D_SIZE 257\n").contains("out of supported range"));
+        assert!(parse_error("LUT_1D_SIZE two\n").contains("not a valid size"));
+        assert!(parse_error("LUT_1D_SIZE 2 2\n").contains("single number"));
+        assert!(parse_error("0 0 0\n1 1 1\n").contains("missing LUT_1D_SIZE"));
+        assert!(parse_error("LUT_1D_SIZE 2\nLUT_3D_SIZE 2\n").contains("both"));
+    }
+    
+    #[test]
+    fn invalid_domain() {
+        let error = parse_error("LUT_1D_SIZE 2\nDOMAIN_MIN 0 1 0\nDOMAIN_MAX 1 1 1\n0 0 0\n1 1 1\n");
+        assert!(error.contains("DOMAIN_MIN has to be lower"), "{error}");
+        assert!(parse_error("DOMAIN_MIN 0 0\n").starts_with("line 1:"));
+    }
+    
+    #[test]
+    fn malformed_lines_report_line_number() {
+        assert!(parse_error("# comment\nLUT_1D_SIZE 2\nSIZE 2\n").starts_with("line 3: unknown keyword"));
+        assert!(parse_error("LUT_1D_SIZE 2\n0 0 0\nTITLE \"late\"\n1 1 1\n").starts_with("line 3: unexpected"));
+        assert!(parse_error("LUT_1D_SIZE 2\n0 0\n1 1 1\n").starts_with("line 2:"));
+    }
+    
+    #[test]
+    fn interpolations_hit_lattice_points() {
+        let color = |[r, g, b]: [f32; 3]| [r * r, 0.5 * g + 0.2 * b, (r + g + b) / 3.0];
+        let lut = lut_3d(5, color);
+        for point in Lut::identity_lattice(5) {
+            assert_close(lut.sample(point, LutInterpolation::Trilinear), color(point));
+            assert_close(lut.sample(point, LutInterpolation::Tetrahedral), color(point));
+        }
+    }
+    
+    #[test]
+    fn interpolations_keep_identity_between_lattice_points() {
+        let lut = lut_3d(3, |c| c);
+        for color in [[0.1, 0.7, 0.3], [0.9, 0.2, 0.55], [0.25, 0.25, 0.75]] {
+            assert_close(lut.sample(color, LutInterpolation::Trilinear), color);
+            assert_close(lut.sample(color, LutInterpolation::Tetrahedral), color);
+        }
+    }
+    
+    #[test]
+    fn colors_outside_domain_are_clamped() {
+        let lut = lut_3d(2, |c| c);
+        assert_close(lut.sample([-1.0, 0.5, 2.0], LutInterpolation::Tetrahedral), [0.0, 0.5, 1.0]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{path::{Path, PathBuf}, sync::Mutex};
+
+/// Presets saved by the user, shared by custom modifiers of all open images.
+///   Filled from config file on start of the application.
+pub static USER_MATRIX_PRESETS: Mutex<MatrixPresets> = Mutex::new(MatrixPresets::new());
+
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
+/// Named grading matrix of the custom modifier
+#[derive(Clone, PartialEq, Debug)]
+pub struct MatrixPreset {
+    pub name: String,
+    
+    /// affine matrix written by rows of red, green, blue and alpha, each ending with offset
+    pub matrix: [f32; 16],
+}
+
+/// Collection of grading matrix presets stored in a file with lines like `Sepia = 0.393 0.769 ...`.
+///   Lines hold either 9 values of 3x3 matrix or 16 values of affine matrix.
+#[derive(Default)]
+pub struct MatrixPresets {
+    presets: Vec<MatrixPreset>,
+    
+    /// config file the presets are saved into after every change
+    path: Option<PathBuf>,
+}
+
+// constructors
+impl MatrixPresets {
+    
+    pub const fn new() -> Self {
+        Self {
+            presets: Vec::new(),
+            path: None,
+        }
+    }
+    
+    /// Loads presets from config file, missing file is treated as empty collection.
+    ///   Returns presets together with descriptions of invalid lines.
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        let (mut presets, problems) = match std::fs::read_to_string(path) {
+            Ok(config) => Self::parse(&config),
+            Err(_) => (Self::new(), vec![]),
+        };
+        presets.path = Some(path.to_path_buf());
+        (presets, problems)
+    }
+    
+    pub fn parse(config: &str) -> (Self, Vec<String>) {
+        let mut presets = Self::new();
+        let mut problems = vec![];
+        
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            
+            // name may contain `=` itself, values never do
+            let (name, values) = match line.rsplit_once('=') {
+                Some((name, values)) if !name.trim().is_empty() => (name.trim(), values),
+                _ => {
+                    problems.push(format!("Presets line {}: expected \"name = matrix values\"", number + 1));
+                    continue;
+                },
+            };
+            
+            let values: Result<Vec<f32>, _> = values.split_whitespace().map(|v| v.parse::<f32>()).collect();
+            match values {
+                Ok(values) if values.len() == 16 => {
+                    let mut matrix = [0.0; 16];
+                    matrix.copy_from_slice(&values);
+                    presets.insert(name, matrix);
+                },
+                Ok(values) if values.len() == 9 => {
+                    let mut matrix = IDENTITY;
+                    for row in 0..3 {
+                        matrix[row * 4..row * 4 + 3].copy_from_slice(&values[row * 3..row * 3 + 3]);
+                    }
+                    presets.insert(name, matrix);
+                },
+                _ => problems.push(format!("Presets line {}: \"{name}\" needs 9 or 16 numbers", number + 1)),
+            }
+        }
+        (presets, problems)
+    }
+}
+
+// properties
+impl MatrixPresets {
+    
+    pub fn presets(&self) -> &[MatrixPreset] {
+        &self.presets
+    }
+    
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# name = grading matrix written by rows, 3x3 or 4x4 with offsets and alpha row\n");
+        for preset in &self.presets {
+            // matrices without offsets and alpha row are written in the shorter form
+            let is_linear = (0..3).all(|row| preset.matrix[row * 4 + 3] == 0.0) && preset.matrix[12..] == IDENTITY[12..];
+            let values: Vec<String> = match is_linear {
+                true => (0..3)
+                    .flat_map(|row| preset.matrix[row * 4..row * 4 + 3].to_vec())
+                    .map(|v| v.to_string())
+                    .collect(),
+                false => preset.matrix.iter().map(|v| v.to_string()).collect(),
+            };
+            config.push_str(&format!("{} = {}\n", preset.name, values.join(" ")));
+        }
+        config
+    }
+    
+    /// Writes all presets into given file
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_config())
+            .map_err(|e| format!("Cannot write presets into {}: {}", path.display(), e))
+    }
+}
+
+// mutating methods
+impl MatrixPresets {
+    
+    /// Adds new preset, preset with the same name is replaced.
+    ///   Names which would be read back as a comment or an empty line are refused.
+    pub fn add(&mut self, name: &str, matrix: [f32; 16]) -> Result<(), String> {
+        check_name(name)?;
+        self.insert(name, matrix);
+        Ok(())
+    }
+    
+    /// Adds preset with a name already known to be valid
+    fn insert(&mut self, name: &str, matrix: [f32; 16]) {
+        match self.presets.iter_mut().find(|p| p.name == name) {
+            Some(preset) => preset.matrix = matrix,
+            None => self.presets.push(MatrixPreset { name: name.to_string(), matrix }),
+        }
+    }
+    
+    /// Renames preset, invalid name or name already used by another preset is refused
+    pub fn rename(&mut self, index: usize, name: &str) -> Result<(), String> {
+        check_name(name)?;
+        if self.presets.iter().enumerate().any(|(i, p)| i != index && p.name == name) {
+            return Err(format!("Preset \"{name}\" already exists"));
+        }
+        if let Some(preset) = self.presets.get_mut(index) {
+            preset.name = name.to_string();
+        }
+        Ok(())
+    }
+    
+    pub fn remove(&mut self, index: usize) {
+        if index < self.presets.len() {
+            self.presets.remove(index);
+        }
+    }
+    
+    /// Adds presets from a file, presets with already used names are replaced.
+    ///   Returns number of imported presets.
+    pub fn import(&mut self, path: &Path) -> Result<usize, String> {
+        let config = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read presets from {}: {}", path.display(), e))?;
+        let (imported, problems) = Self::parse(&config);
+        if let Some(problem) = problems.first() {
+            return Err(format!("Invalid presets file {}: {}", path.display(), problem));
+        }
+        
+        let count = imported.presets.len();
+        for preset in imported.presets {
+            self.insert(&preset.name, preset.matrix);
+        }
+        Ok(count)
+    }
+    
+    /// Writes presets into the config file they were loaded from
+    pub fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        self.export(path)
+    }
+}
+
+/// Preset name has to survive writing into the config file, which trims lines and skips comments
+fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if name.trim_start().starts_with('#') {
+        return Err(format!("Preset name \"{name}\" cannot start with #"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_both_matrix_sizes() {
+        let config = "# comment\nWarm = 1.1 0 0 0 1 0 0 0 0.9\nA = B = 1 0 0 0.1 0 1 0 0 0 0 1 0 0 0 0 0.5\n";
+        let (presets, problems) = MatrixPresets::parse(config);
+        assert!(problems.is_empty(), "{problems:?}");
+        
+        let presets = presets.presets();
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0].name, "Warm");
+        assert_eq!(presets[0].matrix[0], 1.1);
+        assert_eq!(presets[0].matrix[10], 0.9);
+        assert_eq!(presets[0].matrix[12..], IDENTITY[12..]);
+        assert_eq!(presets[1].name, "A = B");
+        assert_eq!(presets[1].matrix[3], 0.1);
+        assert_eq!(presets[1].matrix[15], 0.5);
+    }
+    
+    #[test]
+    fn malformed_lines_are_reported() {
+        let config = "no values\n= 1 0 0 0 1 0 0 0 1\nShort = 1 0 0\nText = 1 0 0 0 one 0 0 0 1\nGood = 1 0 0 0 1 0 0 0 1\n";
+        let (presets, problems) = MatrixPresets::parse(config);
+        assert_eq!(problems.len(), 4, "{problems:?}");
+        assert!(problems[0].starts_with("Presets line 1:"));
+        assert!(problems[1].starts_with("Presets line 2:"));
+        assert!(problems[2].contains("\"Short\" needs 9 or 16 numbers"));
+        assert!(problems[3].contains("\"Text\" needs 9 or 16 numbers"));
+        assert_eq!(presets.presets().len(), 1);
+    }
+    
+    #[test]
+    fn config_round_trip() {
+        let mut presets = MatrixPresets::new();
+        let mut affine = IDENTITY;
+        affine[3] = 0.25;
+        affine[14] = 0.5;
+        presets.add("Identity", IDENTITY).unwrap();
+        presets.add("Affine", affine).unwrap();
+        
+        let (parsed, problems) = MatrixPresets::parse(&presets.to_config());
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(parsed.presets(), presets.presets());
+    }
+    
+    #[test]
+    fn names_read_back_as_comments_are_refused() {
+        let mut presets = MatrixPresets::new();
+        assert!(presets.add("# hidden", IDENTITY).is_err());
+        assert!(presets.add("  ", IDENTITY).is_err());
+        presets.add("Visible", IDENTITY).unwrap();
+        assert!(presets.rename(0, "#hidden").is_err());
+        assert_eq!(presets.presets()[0].name, "Visible");
+    }
+}
@@ -0,0 +1,288 @@
+
+use std::io::Cursor;
+
+use exif::{Exif, Field, In, Tag, Context, Value, experimental::Writer};
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC, jpeg::{JpegSegment, markers}, png::PngChunk};
+use lcms2::{Profile, InfoType, Locale};
+
+const XMP_PACKET_START: &[u8] = b"<x:xmpmeta";
+const XMP_PACKET_END: &[u8] = b"</x:xmpmeta>";
+const XMP_JPEG_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// TIFF files store ICC profile in the primary IFD under this tag
+const TIFF_ICC_PROFILE_TAG: Tag = Tag(Context::Tiff, 0x8773);
+
+/// Tags describing layout of image data in the source file which are invalid for newly encoded image
+const IMAGE_STRUCTURE_TAGS: &[Tag] = &[
+    Tag::ImageWidth,
+    Tag::ImageLength,
+    Tag::BitsPerSample,
+    Tag::Compression,
+    Tag::PhotometricInterpretation,
+    Tag::SamplesPerPixel,
+    Tag::RowsPerStrip,
+    Tag::PlanarConfiguration,
+    Tag::PixelXDimension,
+    Tag::PixelYDimension,
+];
+
+/// What metadata will be written into exported image
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MetadataExport {
+    Keep,
+    StripGps,
+    StripAll,
+}
+
+/// EXIF and XMP metadata of an image file
+#[derive(Default)]
+pub struct ImageMetadata {
+    exif: Option<Exif>,
+    
+    /// Raw XMP packet as it was stored in the file
+    xmp: Option<Vec<u8>>,
+    
+    /// Embedded ICC color profile
+    icc: Option<Vec<u8>>,
+}
+
+// constructors
+impl ImageMetadata {
+    
+    /// Parses metadata from whole content of the file.
+    ///   Missing or broken metadata are ignored because image itself can still be edited without them.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let exif = exif::Reader::new().read_from_container(&mut Cursor::new(bytes)).ok();
+        
+        let icc = match DynImage::from_bytes(Bytes::copy_from_slice(bytes)) {
+            Ok(Some(image)) => image.icc_profile().map(|icc| icc.to_vec()),
+            _ => exif
+                .as_ref()
+                .and_then(|exif| exif.get_field(TIFF_ICC_PROFILE_TAG, In::PRIMARY))
+                .and_then(|field| match &field.value {
+                    Value::Undefined(icc, _) => Some(icc.clone()),
+                    _ => None,
+                }),
+        };
+        
+        Self {
+            exif,
+            xmp: find_xmp_packet(bytes).map(|packet| packet.to_vec()),
+            icc,
+        }
+    }
+}
+
+// properties
+impl ImageMetadata {
+    
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.xmp.is_none()
+    }
+    
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc.as_deref()
+    }
+    
+    /// Name of embedded color profile
+    pub fn icc_description(&self) -> Option<String> {
+        Profile::new_icc(self.icc_profile()?).ok()?.info(InfoType::Description, Locale::none())
+    }
+    
+    pub fn has_xmp(&self) -> bool {
+        self.xmp.is_some()
+    }
+    
+    /// EXIF orientation of the primary image, value 1 means the image is stored upright
+    pub fn orientation(&self) -> u32 {
+        self.exif
+            .as_ref()
+            .and_then(|exif| exif.get_field(Tag::Orientation, In::PRIMARY))
+            .and_then(|field| field.value.get_uint(0))
+            .unwrap_or(1)
+    }
+    
+    pub fn has_gps(&self) -> bool {
+        self.exif.as_ref().is_some_and(|exif| exif.fields().any(|f| f.tag.context() == Context::Gps))
+    }
+    
+    /// Human readable value of a tag from the primary image
+    pub fn field_text(&self, tag: Tag) -> Option<String> {
+        let exif = self.exif.as_ref()?;
+        let field = exif.get_field(tag, In::PRIMARY)?;
+        Some(match &field.value {
+            // display of ascii values is quoted, so it is formatted directly
+            Value::Ascii(values) => values
+                .iter()
+                .map(|v| String::from_utf8_lossy(v).trim().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            _ => field.display_value().with_unit(exif).to_string(),
+        })
+    }
+    
+    /// Key fields of the metadata as (label, value) pairs to be presented to the user
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let join = |tags: &[Tag], separator: &str| {
+            let values: Vec<String> = tags.iter().filter_map(|t| self.field_text(*t)).collect();
+            if values.is_empty() { None } else { Some(values.join(separator)) }
+        };
+        
+        [
+            ("Camera", join(&[Tag::Make, Tag::Model], " ")),
+            ("Lens", join(&[Tag::LensMake, Tag::LensModel], " ")),
+            ("Exposure", join(&[Tag::ExposureTime, Tag::FNumber, Tag::FocalLength], ", ")),
+            ("ISO", self.field_text(Tag::PhotographicSensitivity)),
+            ("Date", self.field_text(Tag::DateTimeOriginal).or_else(|| self.field_text(Tag::DateTime))),
+            ("Artist", self.field_text(Tag::Artist)),
+            ("Copyright", self.field_text(Tag::Copyright)),
+            ("Color profile", self.icc_description()),
+            ("GPS", if self.has_gps() { Some("present".to_string()) } else { None }),
+            ("XMP", if self.has_xmp() { Some("present".to_string()) } else { None }),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some((label, value?)))
+        .collect()
+    }
+}
+
+// export
+impl ImageMetadata {
+    
+    /// Serializes EXIF fields of primary image into TIFF structure as it is stored in image files
+    fn exif_bytes(&self, export: MetadataExport) -> Result<Option<Vec<u8>>, String> {
+        let exif = match &self.exif {
+            Some(exif) if export != MetadataExport::StripAll => exif,
+            _ => return Ok(None),
+        };
+        
+        let mut fields: Vec<&Field> = exif.fields()
+            .filter(|f| f.ifd_num == In::PRIMARY)
+            .filter(|f| !IMAGE_STRUCTURE_TAGS.contains(&f.tag) && f.tag != TIFF_ICC_PROFILE_TAG)
+            .filter(|f| export != MetadataExport::StripGps || f.tag.context() != Context::Gps)
+            .collect();
+        
+        // pixels are rotated on load, so saved image is always upright
+        let upright = Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![1]) };
+        if let Some(orientation) = fields.iter_mut().find(|f| f.tag == Tag::Orientation) {
+            *orientation = &upright;
+        }
+        
+        if fields.is_empty() {
+            return Ok(None);
+        }
+        
+        let mut writer = Writer::new();
+        fields.into_iter().for_each(|f| writer.push_field(f));
+        
+        let mut buffer = Cursor::new(vec![]);
+        writer.write(&mut buffer, exif.little_endian()).map_err(|err| err.to_string())?;
+        Ok(Some(buffer.into_inner()))
+    }
+    
+    /// XMP packet to be written, its orientation is upright like the one in EXIF
+    fn xmp_bytes(&self, export: MetadataExport) -> Option<Vec<u8>> {
+        let xmp = self.xmp.as_deref()?;
+        match export {
+            MetadataExport::Keep => Some(upright_xmp(xmp)),
+            // xmp is not parsed, so location can be removed only with the whole packet
+            MetadataExport::StripGps if !contains(xmp, b"exif:GPS") => Some(upright_xmp(xmp)),
+            _ => None,
+        }
+    }
+    
+    /// Writes metadata into encoded image file.
+    ///   Only JPEG and PNG containers are supported, other files are returned without metadata.
+    pub fn embed(&self, encoded: Vec<u8>, export: MetadataExport) -> Result<Vec<u8>, String> {
+        if export == MetadataExport::StripAll || self.is_empty() {
+            return Ok(encoded);
+        }
+        
+        let exif = self.exif_bytes(export)?;
+        let xmp = self.xmp_bytes(export);
+        
+        if !is_embeddable(&encoded) {
+            return Ok(encoded);
+        }
+        let mut image = match DynImage::from_bytes(Bytes::from(encoded)) {
+            Ok(Some(image)) => image,
+            Ok(None) => return Err("Unknown format of encoded image".to_string()),
+            Err(err) => return Err(err.to_string()),
+        };
+        
+        image.set_exif(exif.map(Bytes::from));
+        
+        if let Some(xmp) = xmp.as_deref() {
+            match &mut image {
+                DynImage::Jpeg(jpeg) => {
+                    let contents = [XMP_JPEG_PREFIX, xmp].concat();
+                    let segments = jpeg.segments_mut();
+                    
+                    // place xmp right after exif or after the first application segment
+                    let position = segments
+                        .iter()
+                        .position(|s| s.marker() == markers::APP1)
+                        .map_or(1, |p| p + 1)
+                        .min(segments.len());
+                    segments.insert(position, JpegSegment::new_with_contents(markers::APP1, Bytes::from(contents)));
+                },
+                DynImage::Png(png) => {
+                    // iTXt: keyword, null separator, no compression, empty language and translated keyword
+                    let contents = [XMP_PNG_KEYWORD, b"\0\0\0\0\0", xmp].concat();
+                    let chunks = png.chunks_mut();
+                    let position = chunks.len().saturating_sub(1);
+                    chunks.insert(position, PngChunk::new(*b"iTXt", Bytes::from(contents)));
+                },
+                _ => {},
+            }
+        }
+        
+        Ok(image.encoder().bytes().to_vec())
+    }
+}
+
+/// Whether encoded file is JPEG or PNG, the only containers metadata and color profile can be embedded into
+pub(super) fn is_embeddable(encoded: &[u8]) -> bool {
+    encoded.starts_with(&[0xFF, 0xD8]) || encoded.starts_with(b"\x89PNG\r\n\x1a\n")
+}
+
+/// Finds XMP packet in raw file content, the packet is plain xml in every container format
+fn find_xmp_packet(bytes: &[u8]) -> Option<&[u8]> {
+    let start = find(bytes, XMP_PACKET_START)?;
+    let end = start + find(&bytes[start..], XMP_PACKET_END)? + XMP_PACKET_END.len();
+    Some(&bytes[start..end])
+}
+
+/// Sets every `tiff:Orientation` value in XMP packet to 1, pixels are rotated on load so saved image is upright
+fn upright_xmp(xmp: &[u8]) -> Vec<u8> {
+    const ORIENTATION: &[u8] = b"tiff:Orientation";
+    let mut result = Vec::with_capacity(xmp.len());
+    let mut rest = xmp;
+    while let Some(position) = find(rest, ORIENTATION) {
+        let (head, tail) = rest.split_at(position + ORIENTATION.len());
+        result.extend_from_slice(head);
+        
+        // value follows either as attribute `="6"` or as element content `>6<`
+        let start = tail
+            .iter()
+            .position(|b| !matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'=' | b'"' | b'\'' | b'>'))
+            .unwrap_or(tail.len());
+        let digits = tail[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+        result.extend_from_slice(&tail[..start]);
+        if digits > 0 {
+            result.push(b'1');
+        }
+        rest = &tail[start + digits..];
+    }
+    result.extend_from_slice(rest);
+    result
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
@@ -2,7 +2,17 @@
 mod multimedia_file;
 mod viewport;
 mod timing;
+mod metadata;
+mod color_profile;
+mod keymap;
+mod lut;
+mod matrix_presets;
 
 pub use multimedia_file::*;
 pub use viewport::*;
 pub use timing::*;
+pub use metadata::*;
+pub use color_profile::*;
+pub use keymap::*;
+pub use lut::*;
+pub use matrix_presets::*;
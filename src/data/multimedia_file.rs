@@ -4,16 +4,25 @@ use std::{
     fmt::{ Display, self },
     ffi::OsStr,
     fs::{File},
-    error::Error, io::Read,
+    error::Error, io::{Read, Seek, SeekFrom, self as io, ErrorKind},
 };
 use file_format::FileFormat;
 use indoc::indoc;
 
+use crate::constants::SUPPORTED_MULTIMEDIA_FILE_FORMATS;
+
+use super::ImageMetadata;
+
 pub struct MultimediaFile {
     path: PathBuf,
     file_name: String,
     format: FileFormat,
     file: File,
+    size: u64,
+    metadata: ImageMetadata,
+    
+    /// content read when the file was opened, it is handed over for decoding without reading the file again
+    content: Option<Vec<u8>>,
 }
 
 /// static methods
@@ -25,16 +34,25 @@ impl MultimediaFile {
         let path = Path::new(filename);
         let path_buf = PathBuf::from(path);
         
-        let format = FileFormat::from_file(path);
-        if let Err(why) = format {
-            return Err(MultimediaFileLoadingError::InvalidFile { path: path_buf, why: Box::new(why) });
+        let format = FileFormat::from_file(path)
+            .map_err(|why| MultimediaFileLoadingError::from_io(&path_buf, why))?;
+        if !SUPPORTED_MULTIMEDIA_FILE_FORMATS.contains(&format) {
+            return Err(MultimediaFileLoadingError::UnsupportedFormat { path: path_buf, format });
         }
         
+        let mut file = File::open(path).map_err(|why| MultimediaFileLoadingError::from_io(&path_buf, why))?;
+        let size = file.metadata().map_err(|why| MultimediaFileLoadingError::from_io(&path_buf, why))?.len();
+        let content = read_content(&mut file, &path_buf, size)?;
+        let metadata = ImageMetadata::from_bytes(&content);
+        
         Ok(MultimediaFile {
-            path: PathBuf::from(path),
+            path: path_buf,
             file_name: format!("{}", path.display()),
-            file: File::open(path).unwrap(),
-            format: format.unwrap(),
+            file,
+            format,
+            size,
+            metadata,
+            content: Some(content),
         })
     }
     
@@ -59,16 +77,39 @@ impl MultimediaFile {
         self.file_name.clone()
     }
     
+    pub fn metadata(&self) -> &ImageMetadata {
+        &self.metadata
+    }
+    
+    /// Size of the file in bytes when it was opened
     pub fn size(&self) -> u64 {
-        self.file.metadata().unwrap().len()
+        self.size
     }
     
-    pub fn bytes(&mut self) -> Vec<u8> {
-        let metadata = self.file.metadata().expect("unable to read metadata");
-        let mut buffer = vec![0; metadata.len() as usize];
-        self.file.read(&mut buffer).expect("buffer overflow");
-        buffer
+    /// Whole content of the file, the content read on opening is returned first and later calls read the file again
+    pub fn bytes(&mut self) -> Result<Vec<u8>, MultimediaFileLoadingError> {
+        match self.content.take() {
+            Some(content) => Ok(content),
+            None => read_content(&mut self.file, &self.path, self.size),
+        }
+    }
+}
+
+/// Reads whole content of the file from its start
+fn read_content(file: &mut File, path: &Path, size: u64) -> Result<Vec<u8>, MultimediaFileLoadingError> {
+    let mut buffer = Vec::with_capacity(size as usize);
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_to_end(&mut buffer))
+        .map_err(|why| MultimediaFileLoadingError::from_io(path, why))?;
+    
+    if (buffer.len() as u64) < size {
+        return Err(MultimediaFileLoadingError::Truncated {
+            path: path.to_path_buf(),
+            expected: size,
+            read: buffer.len() as u64,
+        });
     }
+    Ok(buffer)
 }
 
 impl Display for MultimediaFile {
@@ -90,6 +131,33 @@ pub enum MultimediaFileLoadingError {
         path: PathBuf,
         why: Box<dyn Error>,
     },
+    NotFound {
+        path: PathBuf,
+    },
+    PermissionDenied {
+        path: PathBuf,
+    },
+    /// File is shorter than its size reported by the file system, e.g. it was modified while reading
+    Truncated {
+        path: PathBuf,
+        expected: u64,
+        read: u64,
+    },
+    UnsupportedFormat {
+        path: PathBuf,
+        format: FileFormat,
+    },
+}
+
+impl MultimediaFileLoadingError {
+    fn from_io(path: &Path, why: io::Error) -> Self {
+        let path = path.to_path_buf();
+        match why.kind() {
+            ErrorKind::NotFound => MultimediaFileLoadingError::NotFound { path },
+            ErrorKind::PermissionDenied => MultimediaFileLoadingError::PermissionDenied { path },
+            _ => MultimediaFileLoadingError::InvalidFile { path, why: Box::new(why) },
+        }
+    }
 }
 
 impl Display for MultimediaFileLoadingError {
@@ -97,6 +165,14 @@ impl Display for MultimediaFileLoadingError {
         match self {
             MultimediaFileLoadingError::InvalidFile { path , why } =>
                 write!(f, "Couldn't open {}: {why}", path.display()),
+            MultimediaFileLoadingError::NotFound { path } =>
+                write!(f, "File {} does not exist", path.display()),
+            MultimediaFileLoadingError::PermissionDenied { path } =>
+                write!(f, "Permission to read {} was denied", path.display()),
+            MultimediaFileLoadingError::Truncated { path, expected, read } =>
+                write!(f, "File {} is truncated, read {read} of {expected} bytes", path.display()),
+            MultimediaFileLoadingError::UnsupportedFormat { path, format } =>
+                write!(f, "Format {format} of {} is not supported", path.display()),
         }
     }
 }
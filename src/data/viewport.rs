@@ -23,4 +23,9 @@ impl Viewport {
         self.size = size.into();
         self
     }
+    
+    /// Scale of displayed image relative to size fitting into the viewport, zoom level is in powers of two
+    pub fn scale(&self) -> f32 {
+        2.0_f32.powf(self.zoom_level)
+    }
 }
\ No newline at end of file
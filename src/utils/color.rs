@@ -0,0 +1,47 @@
+
+//! Transfer functions between gamma encoded sRGB and linear light and conversions into other color models.
+//! See: https://en.wikipedia.org/wiki/SRGB#Transformation
+
+/// Converts one normalized sRGB encoded channel value into linear light
+#[inline]
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts one normalized linear light channel value into sRGB encoding
+#[inline]
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts normalized RGB color into hue in degrees, saturation and lightness in range 0.0 - 1.0.
+///   See: https://en.wikipedia.org/wiki/HSL_and_HSV#From_RGB
+pub fn rgb_to_hsl(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let lightness = (max + min) / 2.0;
+    
+    if chroma == 0.0 {
+        return [0.0, 0.0, lightness];
+    }
+    
+    let hue = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+    [hue * 60.0, saturation, lightness]
+}
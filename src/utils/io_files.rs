@@ -1,6 +1,8 @@
 
 use std::path::{PathBuf, Path};
 
+use image::ImageFormat;
+
 use crate::{constants, data::MultimediaFile};
 
 fn get_supported_extensions() -> Vec<&'static str> {
@@ -10,6 +12,30 @@ fn get_supported_extensions() -> Vec<&'static str> {
         .collect()
 }
 
+/// Returns whether file extension belongs to one of supported formats, all aliases like `jpeg` or `tiff` are accepted
+pub fn is_supported_file(path: &Path) -> bool {
+    match ImageFormat::from_path(path) {
+        Ok(format) => constants::SUPPORTED_MULTIMEDIA_FILE_FORMATS
+            .iter()
+            .any(|f| ImageFormat::from_extension(f.extension()) == Some(format)),
+        Err(_) => false,
+    }
+}
+
+/// Lists files of supported formats in directory sorted by name
+pub fn list_supported_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && is_supported_file(path))
+            .collect(),
+        Err(_) => vec![],
+    };
+    files.sort();
+    files
+}
+
 fn common_dialog() -> rfd::FileDialog {
     let extensions = get_supported_extensions();
     let f = rfd::FileDialog::new();
@@ -40,3 +66,40 @@ pub fn save_output_file(original_file: Option<&MultimediaFile>) -> Option<PathBu
     
     d.save_file()
 }
+
+fn lut_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Cube LUT (*.cube)", &["cube"])
+}
+
+/// Asks for a color lookup table file in `.cube` format
+pub fn load_lut_file() -> Option<PathBuf> {
+    lut_dialog().pick_file()
+}
+
+fn presets_dialog() -> rfd::FileDialog {
+    rfd::FileDialog::new().add_filter("Matrix presets (*.conf)", &["conf"])
+}
+
+/// Asks for a file with grading matrix presets to import
+pub fn load_presets_file() -> Option<PathBuf> {
+    presets_dialog().pick_file()
+}
+
+/// Asks where to export grading matrix presets
+pub fn save_presets_file() -> Option<PathBuf> {
+    presets_dialog().set_file_name("matrix_presets.conf").save_file()
+}
+
+/// Asks where to write a color lookup table, proposed name is derived from the image file
+pub fn save_lut_file(original_file: &MultimediaFile) -> Option<PathBuf> {
+    let mut d = lut_dialog();
+    let stem = Path::new(original_file.file_name())
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "look".to_string());
+    d = d.set_file_name(&format!("{}.cube", stem));
+    if let Some(dir) = original_file.get_dir() {
+        d = d.set_directory(dir);
+    }
+    d.save_file()
+}
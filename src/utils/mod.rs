@@ -10,3 +10,4 @@ pub use io_files::*;
 pub use format_size::format_size;
 
 pub mod math;
+pub mod color;
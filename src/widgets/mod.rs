@@ -3,10 +3,12 @@ mod centered_window;
 mod file_dropper;
 mod editor_slider;
 mod buttons;
+mod toasts;
 
 pub use centered_window::*;
 pub use file_dropper::*;
 pub use editor_slider::*;
 pub use buttons::*;
+pub use toasts::*;
 
 pub mod texts;
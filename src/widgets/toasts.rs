@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use egui::{Align2, Color32, vec2};
+
+/// Time after which info and warning toasts disappear, errors stay until dismissed
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+const TOAST_WIDTH: f32 = 320.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    fn icon(&self) -> &'static str {
+        match self {
+            ToastKind::Info => "ℹ",
+            ToastKind::Warning => "⚠",
+            ToastKind::Error => "❌",
+        }
+    }
+    
+    fn color(&self) -> Color32 {
+        match self {
+            ToastKind::Info => Color32::LIGHT_BLUE,
+            ToastKind::Warning => Color32::GOLD,
+            ToastKind::Error => Color32::LIGHT_RED,
+        }
+    }
+}
+
+struct Toast {
+    kind: ToastKind,
+    message: String,
+    created: Instant,
+}
+
+/// Stack of dismissible notifications rendered over the bottom right corner of the window
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    
+    pub fn add(&mut self, kind: ToastKind, message: impl Into<String>) {
+        self.toasts.push(Toast { kind, message: message.into(), created: Instant::now() });
+    }
+    
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.add(ToastKind::Info, message);
+    }
+    
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.add(ToastKind::Warning, message);
+    }
+    
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.add(ToastKind::Error, message);
+    }
+    
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        self.toasts.retain(|t| t.kind == ToastKind::Error || t.created.elapsed() < TOAST_DURATION);
+        if self.toasts.is_empty() {
+            return;
+        }
+        
+        let mut dismissed = None;
+        egui::Area::new("toasts")
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(TOAST_WIDTH);
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.set_width(TOAST_WIDTH);
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(toast.kind.icon()).size(20.0).color(toast.kind.color()));
+                            ui.add(egui::Label::new(toast.message.as_str()).wrap(true));
+                            ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                                if ui.small_button("✖").clicked() {
+                                    dismissed = Some(i);
+                                }
+                            });
+                        });
+                    });
+                }
+            });
+        
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+        
+        // keep repainting so expired toasts disappear without user input
+        if self.toasts.iter().any(|t| t.kind != ToastKind::Error) {
+            ctx.request_repaint();
+        }
+    }
+}